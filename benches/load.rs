@@ -0,0 +1,72 @@
+//! Compares load times of the RON & binary prefab formats.
+//!
+//! `cargo bench --bench load -- [entities]`, 10 000 entities by default.
+
+use std::{path::Path, time::{Duration, Instant}};
+
+use bevy::prelude::*;
+use bevy_scene_test::{deserialize_prefab, serialize_prefab, binary::serialize_binary_prefab, extract::{ExtractOptions, extract_prefab}, lenient::PrefabLoadMode, format::FormatMigrations, error::PrefabError};
+
+/// Loads per format, after one warm-up load.
+const RUNS: u32 = 10;
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+struct BenchComponent {
+    name: String
+}
+
+fn main() -> anyhow::Result<()> {
+    let entity_count = std::env::args().skip(1).find_map(|arg| arg.parse().ok()).unwrap_or(10_000);
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+        .register_type::<BenchComponent>();
+
+    run_load_benchmark(&mut app.world, entity_count)
+}
+
+/// Times loading a prefab of `entity_count` entities from RON & from binary, printing the results.
+fn run_load_benchmark(world: &mut World, entity_count: usize) -> anyhow::Result<()> {
+    let root = world.spawn((
+        BenchComponent {
+            name: "Root".to_owned()
+        },
+        TransformBundle::default()
+    )).id();
+
+    let children = (1..entity_count).map(|i| world.spawn((
+        BenchComponent {
+            name: format!("Entity {i}")
+        },
+        TransformBundle::from_transform(Transform::from_xyz(i as f32, 0.0, 0.0))
+    )).id()).collect::<Vec<_>>();
+    world.entity_mut(root).push_children(&children);
+
+    let prefab = extract_prefab(world, root, &ExtractOptions::named("Benchmark"))?;
+    let type_registry = world.resource::<AppTypeRegistry>().0.clone();
+
+    let ron = serialize_prefab(&prefab, &type_registry)?;
+    let binary = serialize_binary_prefab(&prefab, &type_registry)?;
+    let migrations = FormatMigrations::default();
+
+    let ron_time = time_loads(|| deserialize_prefab(ron.as_bytes(), Path::new("benchmark.prefab"), &type_registry, PrefabLoadMode::Strict, &migrations).map(drop))?;
+    let binary_time = time_loads(|| deserialize_prefab(&binary, Path::new("benchmark.prefab.bin"), &type_registry, PrefabLoadMode::Strict, &migrations).map(drop))?;
+
+    println!("Loading a {entity_count} entity prefab, average of {RUNS} runs:");
+    println!("  RON:    {:>10} bytes, {ron_time:?}", ron.len());
+    println!("  binary: {:>10} bytes, {binary_time:?}", binary.len());
+
+    Ok(())
+}
+
+fn time_loads(mut load: impl FnMut() -> Result<(), PrefabError>) -> Result<Duration, PrefabError> {
+    load()?;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        load()?;
+    }
+
+    Ok(start.elapsed() / RUNS)
+}
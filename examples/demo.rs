@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_scene_test::{PrefabPlugin, PrefabResources, LeafNode, serialize_prefab, extract::{ExtractOptions, extract_prefab}};
+
+fn main() {
+    let mut app = App::new();
+
+    app.add_plugins((DefaultPlugins, PrefabPlugin));
+
+    app.register_type::<TestComponent>();
+
+    app.add_systems(Startup, spawn_world_system);
+    app.add_systems(PostStartup, serialize_world_system);
+
+    app.run();
+}
+
+#[derive(Resource)]
+struct SceneToSave(Entity);
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+struct TestComponent {
+    name: String
+}
+
+fn spawn_world_system(
+    mut commands: Commands
+) {
+    let scene = commands.spawn((
+        TestComponent {
+            name: "Steve".to_owned()
+        },
+        TransformBundle::default()
+    )).with_children(|child_builer| {
+        child_builer.spawn((
+            TestComponent {
+                name: "Stove".to_owned()
+            },
+            TransformBundle::from_transform(Transform::from_xyz(1.0, 0.5, -1.3)),
+            LeafNode
+        )).with_children(|child_builder| {
+            child_builder.spawn(TransformBundle::from_transform(Transform::from_xyz(0.0, 5.0, 0.0)));
+        });
+    }).id();
+
+    commands.insert_resource(SceneToSave(scene));
+}
+
+fn serialize_world_system(
+    world: &World
+) {
+    let entity_to_save = world.resource::<SceneToSave>().0;
+
+    let options = ExtractOptions::named("Test")
+        .with_resource_filter(world.resource::<PrefabResources>().0.clone());
+
+    let prefab = match extract_prefab(world, entity_to_save, &options) {
+        Ok(prefab) => prefab,
+        Err(err) => {
+            error!("Failed to extract prefab: {err}");
+            return;
+        }
+    };
+
+    let type_registry = world.resource::<AppTypeRegistry>();
+
+    match serialize_prefab(&prefab, type_registry) {
+        Ok(serialized_prefab) => info!("Serialized: {serialized_prefab}"),
+        Err(err) => error!("Failed to serialize prefab: {err}")
+    }
+}
@@ -0,0 +1,19 @@
+//! Validates, formats & converts prefab files without opening a window, e.g. to reject broken prefabs in CI.
+//!
+//! `cargo run --features cli --bin prefab -- validate assets`
+
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use bevy_scene_test::{LeafNode, PrefabMarker, cli};
+
+fn main() -> ExitCode {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+        .register_type::<PrefabMarker>()
+        .register_type::<LeafNode>();
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    cli::run(&app, &args)
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use bevy::{prelude::*, asset::{AssetLoader, LoadContext}, reflect::TypeRegistryArc, utils::BoxedFuture};
+use bincode::Options;
+
+use crate::{Prefab, PrefabSerializer, PrefabDeserializer, load_prefab, error::{PrefabError, DeserializeErrors}, format::{PrefabFormat, FormatMigrations}, lenient::PrefabLoadMode, tree::PrefabLayout};
+
+/// Loads `.prefab.bin` files, the binary encoding of the same format as `.prefab` files.
+///
+/// Binary prefabs are always loaded in [`PrefabLoadMode::Strict`], skipping values needs a self-describing format.
+#[derive(Debug)]
+pub struct BinaryPrefabLoader {
+    type_registry: TypeRegistryArc,
+    migrations: FormatMigrations,
+}
+impl FromWorld for BinaryPrefabLoader {
+    fn from_world(world: &mut World) -> Self {
+        let type_registry = world.resource::<AppTypeRegistry>();
+
+        BinaryPrefabLoader {
+            type_registry: type_registry.0.clone(),
+            migrations: world.get_resource::<FormatMigrations>().cloned().unwrap_or_default()
+        }
+    }
+}
+impl AssetLoader for BinaryPrefabLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(load_prefab(bytes, load_context, &self.type_registry, PrefabLoadMode::Strict, &self.migrations))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        const EXTENSIONS: &[&str] = &[PrefabFormat::Binary.extension()];
+        EXTENSIONS
+    }
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+/// Binary files are always written in the current format version, any that need `migrations` fail to load.
+pub fn deserialize_binary_prefab(bytes: &[u8], path: &Path, type_registry: &TypeRegistryArc, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let errors = DeserializeErrors::default();
+    let prefab_deserializer = PrefabDeserializer {
+        type_registry,
+        mode: PrefabLoadMode::Strict,
+        migrations,
+        errors: &errors
+    };
+
+    bincode_options()
+        .deserialize_seed(prefab_deserializer, bytes)
+        .map_err(|err| match *err {
+            // Binary files have no lines to point at.
+            bincode::ErrorKind::Custom(message) => errors.error(path, None, message),
+            err => PrefabError::Parse {
+                path: path.to_path_buf(),
+                position: None,
+                message: err.to_string()
+            }
+        })
+}
+
+/// Serializes `prefab` into the bytes stored in `.prefab.bin` files.
+pub fn serialize_binary_prefab(prefab: &Prefab, registry: &TypeRegistryArc) -> Result<Vec<u8>, bincode::Error> {
+    let prefab_serializer = PrefabSerializer {
+        prefab,
+        registry,
+        canonical: true,
+        layout: PrefabLayout::Flat
+    };
+
+    bincode_options().serialize(&prefab_serializer)
+}
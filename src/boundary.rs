@@ -0,0 +1,49 @@
+use std::any::TypeId;
+
+use bevy::{prelude::*, reflect::{FromType, TypeRegistryInternal}};
+
+/// What prefab extraction does when it reaches an entity with a boundary component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PrefabBoundaryKind {
+    /// Save the entity, but not its children.
+    DropChildren,
+    /// Save the entity as a reference to the prefab it was spawned from, instead of its contents.
+    NestedPrefab,
+    /// Don't save the entity or anything below it, for runtime-only entities like VFX.
+    Skip,
+}
+
+/// Marks a component as a prefab boundary.
+///
+/// Register it with `#[reflect(PrefabBoundary)]` so extraction picks it up from the type registry.
+pub trait PrefabBoundary {
+    const BOUNDARY: PrefabBoundaryKind;
+}
+
+/// Type data for components that are prefab boundaries.
+///
+/// Can also be inserted into a registration directly to mark types that don't implement [`PrefabBoundary`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectPrefabBoundary {
+    pub kind: PrefabBoundaryKind,
+}
+impl<T: PrefabBoundary> FromType<T> for ReflectPrefabBoundary {
+    fn from_type() -> Self {
+        Self {
+            kind: T::BOUNDARY
+        }
+    }
+}
+
+/// The boundary kind of `entity`, if any of its components are boundaries.
+///
+/// `extra` is checked before the type registry. When several components apply the strongest wins, [`PrefabBoundaryKind::Skip`] over [`PrefabBoundaryKind::NestedPrefab`] over [`PrefabBoundaryKind::DropChildren`].
+pub fn boundary_of(world: &World, entity: EntityRef, extra: &[(TypeId, PrefabBoundaryKind)], type_registry: &TypeRegistryInternal) -> Option<PrefabBoundaryKind> {
+    entity.archetype().components()
+        .filter_map(|component_id| world.components().get_info(component_id)?.type_id())
+        .filter_map(|type_id| {
+            extra.iter().find(|(extra_type_id, _)| *extra_type_id == type_id).map(|(_, kind)| *kind)
+                .or_else(|| type_registry.get_type_data::<ReflectPrefabBoundary>(type_id).map(|boundary| boundary.kind))
+        })
+        .max()
+}
@@ -0,0 +1,207 @@
+use bevy::{prelude::*, reflect::{ReflectRef, TypeRegistryArc, serde::TypedReflectSerializer}, scene::{DynamicEntity, serde::{ENTITY_STRUCT, ENTITY_FIELD_COMPONENTS}}, utils::{HashMap, HashSet}};
+use serde::{Serialize, ser::{SerializeMap, SerializeStruct}};
+
+use crate::{Prefab, migration::versioned_type_path};
+
+/// Sorts `prefab` the way saved files are: entities in hierarchy order, components & resources by type path, nested prefabs by entity.
+pub fn canonicalize(prefab: &mut Prefab) {
+    let order = hierarchy_order(prefab).into_iter()
+        .enumerate()
+        .map(|(index, entity)| (entity.entity, index))
+        .collect::<HashMap<_, _>>();
+
+    prefab.scene.entities.sort_by_key(|entity| order.get(&entity.entity).copied());
+
+    for entity in &mut prefab.scene.entities {
+        entity.components.sort_by(|a, b| a.type_name().cmp(b.type_name()));
+    }
+    prefab.scene.resources.sort_by(|a, b| a.type_name().cmp(b.type_name()));
+
+    prefab.nested.sort_by_key(|nested| order.get(&nested.entity).copied());
+}
+
+/// Entities of the prefab starting at the root, then depth-first in [`Children`] order.
+///
+/// Entities that can't be reached from the root come last, in their current order.
+pub fn hierarchy_order(prefab: &Prefab) -> Vec<&DynamicEntity> {
+    let entities = entities_by_id(&prefab.scene.entities);
+    let mut ordered = Vec::with_capacity(prefab.scene.entities.len());
+    let mut visited = HashSet::new();
+
+    let mut entities_to_visit = prefab.root().into_iter().collect::<Vec<_>>();
+    while let Some(entity) = entities_to_visit.pop() {
+        let Some(&dynamic_entity) = entities.get(&entity) else {
+            continue;
+        };
+        if !visited.insert(entity) {
+            continue;
+        }
+
+        ordered.push(dynamic_entity);
+        entities_to_visit.extend(children_of(dynamic_entity).into_iter().rev());
+    }
+
+    ordered.extend(prefab.scene.entities.iter().filter(|entity| !visited.contains(&entity.entity)));
+
+    ordered
+}
+
+/// Looks up entities by their id, for prefabs too large to search through for each one.
+pub(crate) fn entities_by_id<'a>(entities: impl IntoIterator<Item = &'a DynamicEntity>) -> HashMap<Entity, &'a DynamicEntity> {
+    entities.into_iter().map(|entity| (entity.entity, entity)).collect()
+}
+
+/// Reads the entities stored in a saved [`Children`] component.
+pub(crate) fn children_of(entity: &DynamicEntity) -> Vec<Entity> {
+    let Some(children) = entity.components.iter().find(|component| component.type_name() == std::any::type_name::<Children>()) else {
+        return Vec::default();
+    };
+    let ReflectRef::TupleStruct(children) = children.reflect_ref() else {
+        return Vec::default();
+    };
+    let Some(ReflectRef::List(children)) = children.field(0).map(|field| field.reflect_ref()) else {
+        return Vec::default();
+    };
+
+    children.iter().filter_map(|child| child.downcast_ref::<Entity>()).cloned().collect()
+}
+
+fn sorted_by_type_name(entries: &[Box<dyn Reflect>]) -> Vec<&dyn Reflect> {
+    let mut sorted = entries.iter().map(|entry| &**entry).collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.type_name().cmp(b.type_name()));
+
+    sorted
+}
+
+/// Same output as [`bevy::scene::serde::EntitiesSerializer`], but entities in hierarchy order & components sorted by type path.
+pub struct CanonicalEntitiesSerializer<'a> {
+    pub prefab: &'a Prefab,
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for CanonicalEntitiesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let entities = hierarchy_order(self.prefab);
+        let mut state = serializer.serialize_map(Some(entities.len()))?;
+
+        for entity in entities {
+            state.serialize_entry(
+                &entity.entity,
+                &EntitySerializer {
+                    entity,
+                    registry: self.registry,
+                    canonical: true
+                }
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+/// Same output as [`bevy::scene::serde::EntitiesSerializer`], but with versions on types that have migrations.
+pub struct VersionedEntitiesSerializer<'a> {
+    pub entities: &'a [DynamicEntity],
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for VersionedEntitiesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_map(Some(self.entities.len()))?;
+
+        for entity in self.entities {
+            state.serialize_entry(
+                &entity.entity,
+                &EntitySerializer {
+                    entity,
+                    registry: self.registry,
+                    canonical: false
+                }
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+struct EntitySerializer<'a> {
+    entity: &'a DynamicEntity,
+    registry: &'a TypeRegistryArc,
+    canonical: bool,
+}
+impl<'a> Serialize for EntitySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_struct(ENTITY_STRUCT, 1)?;
+
+        if self.canonical {
+            state.serialize_field(
+                ENTITY_FIELD_COMPONENTS,
+                &CanonicalMapSerializer {
+                    entries: &self.entity.components,
+                    registry: self.registry
+                }
+            )?;
+        } else {
+            state.serialize_field(
+                ENTITY_FIELD_COMPONENTS,
+                &VersionedMapSerializer {
+                    entries: &self.entity.components,
+                    registry: self.registry
+                }
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+/// Same output as [`bevy::scene::serde::SceneMapSerializer`], but sorted by type path & with versions on types that have migrations.
+pub struct CanonicalMapSerializer<'a> {
+    pub entries: &'a [Box<dyn Reflect>],
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for CanonicalMapSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        serialize_versioned_map(sorted_by_type_name(self.entries), self.registry, serializer)
+    }
+}
+
+/// Same output as [`bevy::scene::serde::SceneMapSerializer`], but with versions on types that have migrations.
+///
+/// Keys without a version are read back as version 0, so values of types with migrations have to be written with theirs.
+pub struct VersionedMapSerializer<'a> {
+    pub entries: &'a [Box<dyn Reflect>],
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for VersionedMapSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        serialize_versioned_map(self.entries.iter().map(|entry| &**entry).collect(), self.registry, serializer)
+    }
+}
+
+fn serialize_versioned_map<S: serde::Serializer>(entries: Vec<&dyn Reflect>, registry: &TypeRegistryArc, serializer: S) -> Result<S::Ok, S::Error> {
+    let registry = registry.read();
+    let mut state = serializer.serialize_map(Some(entries.len()))?;
+
+    for reflect in entries {
+        state.serialize_entry(
+            &versioned_type_path(reflect, &registry),
+            &TypedReflectSerializer::new(reflect, &registry)
+        )?;
+    }
+
+    state.end()
+}
@@ -0,0 +1,282 @@
+use std::{fs, path::{Path, PathBuf}, process::ExitCode};
+
+use bevy::{prelude::*, asset::AssetPath, reflect::TypeRegistryArc};
+
+use crate::{Prefab, deserialize_prefab, error::PrefabError, format::{self, PrefabFormat, FormatMigrations}, lenient::PrefabLoadMode, nested::check_nested_entities, tree::PrefabLayout, variant};
+
+const USAGE: &str = "\
+usage: prefab validate [--assets <folder>] <files or folders>...
+       prefab fmt [--check] <files or folders>...
+       prefab convert --to <ron|json|binary> <files or folders>...
+       prefab upgrade <files or folders>...";
+
+/// What the commands read prefabs with, taken from the app.
+struct Loader {
+    type_registry: TypeRegistryArc,
+    migrations: FormatMigrations,
+}
+impl Loader {
+    fn read(&self, path: &Path) -> Result<Prefab, PrefabError> {
+        let bytes = fs::read(path).map_err(|err| PrefabError::io(path, err))?;
+
+        deserialize_prefab(&bytes, path, &self.type_registry, PrefabLoadMode::Strict, &self.migrations)
+    }
+
+    /// Reads the file at `path` & flattens it onto its chain of bases the way the loader does, bases being read from `assets`.
+    fn read_flattened(&self, path: &Path, assets: &Path) -> Result<Prefab, PrefabError> {
+        let mut chain = vec![path.to_path_buf()];
+        let mut variants = Vec::new();
+        let mut prefab = self.read(path)?;
+
+        while let Some(variant) = prefab.variant.take() {
+            let base_file = assets.join(AssetPath::from(variant.base.as_str()).path());
+
+            if chain.contains(&base_file) {
+                chain.push(base_file);
+                return Err(PrefabError::Cycle {
+                    chain
+                });
+            }
+
+            let base = self.read(&base_file)?;
+
+            variants.push((chain[chain.len() - 1].clone(), prefab, variant));
+            chain.push(base_file);
+            prefab = base;
+        }
+
+        variant::flatten_variants(prefab, variants, &self.type_registry)
+    }
+}
+
+/// Folder nested prefabs & bases are looked up in when validating, same as bevy's default asset folder.
+const ASSET_FOLDER: &str = "assets";
+
+/// Runs the `prefab` tool with `args`, the command line without the program name, against the types & [`FormatMigrations`] registered in `app`.
+///
+/// The `prefab` binary only knows the types of bevy & this crate. Games register their own types on `app` & call this from a binary of their own.
+pub fn run(app: &App, args: &[String]) -> ExitCode {
+    let loader = Loader {
+        type_registry: app.world.resource::<AppTypeRegistry>().0.clone(),
+        migrations: app.world.get_resource::<FormatMigrations>().cloned().unwrap_or_default()
+    };
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let failures = match args.as_slice() {
+        ["validate", "--assets", assets, paths @ ..] if !paths.is_empty() => validate(paths, Path::new(assets), &loader),
+        ["validate", paths @ ..] if !paths.is_empty() => validate(paths, Path::new(ASSET_FOLDER), &loader),
+        ["fmt", "--check", paths @ ..] if !paths.is_empty() => fmt(paths, true, &loader),
+        ["fmt", paths @ ..] if !paths.is_empty() => fmt(paths, false, &loader),
+        ["upgrade", paths @ ..] if !paths.is_empty() => upgrade(paths, &loader),
+        ["convert", "--to", to, paths @ ..] if !paths.is_empty() => match format_named(to) {
+            Some(to) => convert(paths, to, &loader),
+            None => {
+                eprintln!("unknown format `{to}`\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match failures {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE
+    }
+}
+
+fn format_named(name: &str) -> Option<PrefabFormat> {
+    match name {
+        "ron" => Some(PrefabFormat::Ron),
+        "json" => Some(PrefabFormat::Json),
+        "binary" => Some(PrefabFormat::Binary),
+        _ => None
+    }
+}
+
+/// Loads every file strictly along with the prefabs it nests or is a variant of, printing `file:line:column: error` for each that fails.
+///
+/// Nested prefabs & bases are asset paths, read from the `assets` folder. Returns the number of failures.
+fn validate(paths: &[&str], assets: &Path, loader: &Loader) -> usize {
+    let files = prefab_files(paths);
+    let count = files.len();
+
+    let failures = files.into_iter()
+        .filter_map(|file| file.and_then(|file| validate_file(&file, assets, loader)).err())
+        .inspect(|err| eprintln!("{err}"))
+        .count();
+
+    println!("{count} prefab files checked, {failures} invalid");
+    failures
+}
+
+fn validate_file(path: &Path, assets: &Path, loader: &Loader) -> Result<(), PrefabError> {
+    validate_references(path, assets, loader, &mut vec![path.to_path_buf()])
+}
+
+/// Checks that the file at `path`, flattened onto its bases, & the prefabs it nests in turn, exist & load. `chain` holds the files that led to `path`.
+fn validate_references(path: &Path, assets: &Path, loader: &Loader, chain: &mut Vec<PathBuf>) -> Result<(), PrefabError> {
+    let prefab = loader.read_flattened(path, assets)?;
+    check_nested_entities(&prefab, path)?;
+
+    for nested in &prefab.nested {
+        let file = assets.join(AssetPath::from(nested.path.as_str()).path());
+
+        if chain.contains(&file) {
+            chain.push(file);
+            return Err(PrefabError::Cycle {
+                chain: chain.clone()
+            });
+        }
+
+        chain.push(file.clone());
+        validate_references(&file, assets, loader, chain)?;
+        chain.pop();
+    }
+
+    Ok(())
+}
+
+/// Rewrites every file the way the prefab serializer writes it, or with `check` only lists the files that would change.
+///
+/// Returns the number of files that failed, or that aren't formatted when checking.
+fn fmt(paths: &[&str], check: bool, loader: &Loader) -> usize {
+    let mut failures = 0;
+
+    for file in prefab_files(paths) {
+        match file.and_then(|file| fmt_file(&file, check, loader).map(|changed| (file, changed))) {
+            Ok((file, true)) if check => {
+                println!("{} isn't formatted", file.display());
+                failures += 1;
+            }
+            Ok((file, true)) => println!("formatted {}", file.display()),
+            Ok((_, false)) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+/// Whether the file at `path` isn't formatted, rewriting it unless `check` is set.
+fn fmt_file(path: &Path, check: bool, loader: &Loader) -> Result<bool, PrefabError> {
+    let bytes = fs::read(path).map_err(|err| PrefabError::io(path, err))?;
+
+    let layout = match PrefabFormat::from_path(path) {
+        PrefabFormat::Binary => PrefabLayout::Flat,
+        _ => format::read_header(&bytes, path)?.1
+    };
+
+    let prefab = deserialize_prefab(&bytes, path, &loader.type_registry, PrefabLoadMode::Strict, &loader.migrations)?;
+    let formatted = format::serialize_prefab_file(&prefab, path, layout, &loader.type_registry)?;
+
+    if formatted == bytes {
+        return Ok(false);
+    }
+    if !check {
+        format::replace_file(path, &formatted)?;
+    }
+
+    Ok(true)
+}
+
+/// Rewrites every RON or JSON file in the current format version, migrating older ones. Returns the number of failures.
+fn upgrade(paths: &[&str], loader: &Loader) -> usize {
+    let mut failures = 0;
+
+    for file in prefab_files(paths) {
+        match file.and_then(|file| format::upgrade_prefab_file(&file, &loader.type_registry, &loader.migrations).map(|upgraded| (file, upgraded))) {
+            Ok((file, true)) => println!("upgraded {} to format version {}", file.display(), format::FORMAT_VERSION),
+            Ok((_, false)) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+/// Writes each file next to itself in the `to` format, skipping files that already are. Returns the number of failures.
+fn convert(paths: &[&str], to: PrefabFormat, loader: &Loader) -> usize {
+    let mut failures = 0;
+
+    for file in prefab_files(paths) {
+        let result = file.and_then(|file| {
+            if PrefabFormat::from_path(&file) == to {
+                return Ok(None);
+            }
+
+            let output = converted_path(&file, to);
+            format::convert_prefab_file(&file, &output, &loader.type_registry, &loader.migrations)?;
+
+            Ok(Some((file, output)))
+        });
+
+        match result {
+            Ok(Some((file, output))) => println!("converted {} to {}", file.display(), output.display()),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+/// `levels/goblin.prefab` -> `levels/goblin.prefab.json`
+fn converted_path(path: &Path, to: PrefabFormat) -> PathBuf {
+    let file_name = path.file_name().map(|file_name| file_name.to_string_lossy()).unwrap_or_default();
+    let extension = format!(".{}", PrefabFormat::from_path(path).extension());
+    let stem = file_name.strip_suffix(&extension).unwrap_or(&file_name);
+
+    path.with_file_name(format!("{stem}.{}", to.extension()))
+}
+
+fn is_prefab_file(path: &Path) -> bool {
+    let file_name = path.file_name().map(|file_name| file_name.to_string_lossy()).unwrap_or_default();
+
+    [PrefabFormat::Ron, PrefabFormat::Binary, PrefabFormat::Json].into_iter()
+        .any(|format| file_name.ends_with(&format!(".{}", format.extension())))
+}
+
+/// The files in `paths`, along with the prefab files anywhere in the folders in `paths`, in a stable order.
+fn prefab_files(paths: &[&str]) -> Vec<Result<PathBuf, PrefabError>> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_prefab_files(Path::new(path), true, &mut files);
+    }
+
+    files
+}
+
+/// Files named on the command line are always kept, even without a prefab extension.
+fn collect_prefab_files(path: &Path, named: bool, files: &mut Vec<Result<PathBuf, PrefabError>>) {
+    if !path.is_dir() {
+        if named || is_prefab_file(path) {
+            files.push(Ok(path.to_path_buf()));
+        }
+        return;
+    }
+
+    let mut entries = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect::<Vec<_>>(),
+        Err(err) => {
+            files.push(Err(PrefabError::io(path, err)));
+            return;
+        }
+    };
+    entries.sort();
+
+    for entry in entries {
+        collect_prefab_files(&entry, false, files);
+    }
+}
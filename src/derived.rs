@@ -0,0 +1,74 @@
+use std::any::TypeId;
+
+use bevy::{prelude::*, ecs::world::EntityMut, scene::SceneFilter};
+
+/// A runtime component that isn't saved into prefabs, as it's derived from a saved one.
+#[derive(Debug, Clone, Copy)]
+pub struct DerivedComponent {
+    /// The component that is saved.
+    pub saved: TypeId,
+    /// The component added alongside it on spawn.
+    pub derived: TypeId,
+    insert: fn(&mut EntityMut),
+}
+
+/// Pairs of (saved component -> derived component).
+///
+/// Derived components are left out when extracting prefabs & inserted with their defaults when spawning entities with the saved component, e.g. [`GlobalTransform`] for [`Transform`].
+#[derive(Resource, Debug, Clone)]
+pub struct DerivedComponents(Vec<DerivedComponent>);
+impl Default for DerivedComponents {
+    fn default() -> Self {
+        let mut derived_components = Self(Vec::new());
+
+        derived_components
+            .add::<Transform, GlobalTransform>()
+            .add::<Visibility, ComputedVisibility>();
+
+        derived_components
+    }
+}
+impl DerivedComponents {
+    /// Inserts `Derived` on spawned entities that have `Saved`, and stops `Derived` from being saved.
+    pub fn add<Saved: Component, Derived: Component + Default>(&mut self) -> &mut Self {
+        self.0.push(DerivedComponent {
+            saved: TypeId::of::<Saved>(),
+            derived: TypeId::of::<Derived>(),
+            insert: |entity| {
+                entity.insert(Derived::default());
+            }
+        });
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DerivedComponent> {
+        self.0.iter()
+    }
+
+    /// Denies every derived component in `filter`.
+    pub fn deny_derived(&self, filter: &mut SceneFilter) {
+        for derived_component in &self.0 {
+            filter.deny_by_id(derived_component.derived);
+        }
+    }
+
+    /// Inserts the derived components `entity` is missing.
+    pub fn insert_derived(&self, entity: &mut EntityMut) {
+        for derived_component in &self.0 {
+            if entity.contains_type_id(derived_component.saved) && !entity.contains_type_id(derived_component.derived) {
+                (derived_component.insert)(entity);
+            }
+        }
+    }
+}
+
+/// Inserts missing derived components on each of `entities`, using the world's [`DerivedComponents`] if there is one.
+pub fn insert_derived_components(world: &mut World, entities: impl IntoIterator<Item = Entity>) {
+    let derived_components = world.get_resource::<DerivedComponents>().cloned().unwrap_or_default();
+
+    for entity in entities {
+        if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+            derived_components.insert_derived(&mut entity_mut);
+        }
+    }
+}
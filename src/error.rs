@@ -0,0 +1,270 @@
+use std::{cell::Cell, fmt, io, path::{Path, PathBuf}};
+
+use bevy::{prelude::Entity, scene::SceneSpawnError};
+
+/// A line & column in a text prefab file, both starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Everything that can go wrong loading, saving, extracting or spawning a prefab.
+#[derive(Debug)]
+pub enum PrefabError {
+    /// The file isn't valid in its format, or isn't shaped like a prefab.
+    Parse {
+        path: PathBuf,
+        /// Binary files have no lines, so their errors have no position.
+        position: Option<Position>,
+        message: String,
+    },
+    /// A component or resource whose type isn't registered.
+    UnknownType {
+        /// `None` for prefabs that weren't loaded from a file.
+        path: Option<PathBuf>,
+        position: Option<Position>,
+        type_path: String,
+    },
+    /// A field that has to be in the file isn't.
+    MissingField {
+        path: PathBuf,
+        position: Option<Position>,
+        field: String,
+    },
+    /// An entity that isn't part of the prefab or the world.
+    InvalidEntity {
+        path: Option<PathBuf>,
+        /// `None` if the prefab has no root entity at all.
+        entity: Option<Entity>,
+        reason: String,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A format migration, or a migration of one of the components, failed.
+    Migration {
+        path: PathBuf,
+        message: String,
+    },
+    /// Prefabs that nest themselves or are their own base, in the order they were reached.
+    Cycle {
+        chain: Vec<PathBuf>,
+    },
+    /// A prefab, or one nested in it, failed to load or was unloaded before it could be spawned.
+    NotLoaded {
+        path: Option<PathBuf>,
+    },
+    /// Writing a prefab into the world failed for another reason than an unregistered type.
+    Spawn {
+        path: Option<PathBuf>,
+        source: SceneSpawnError,
+    },
+    /// A file in a format whose cargo feature isn't enabled.
+    FeatureDisabled {
+        path: PathBuf,
+        feature: &'static str,
+    },
+    /// A prefab that can't be written in the requested format or layout.
+    Serialize(String),
+}
+impl PrefabError {
+    /// The file the error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            PrefabError::Parse { path, .. }
+            | PrefabError::MissingField { path, .. }
+            | PrefabError::Io { path, .. }
+            | PrefabError::Migration { path, .. }
+            | PrefabError::FeatureDisabled { path, .. } => Some(path),
+            PrefabError::UnknownType { path, .. }
+            | PrefabError::InvalidEntity { path, .. }
+            | PrefabError::NotLoaded { path }
+            | PrefabError::Spawn { path, .. } => path.as_deref(),
+            PrefabError::Cycle { chain } => chain.first().map(PathBuf::as_path),
+            PrefabError::Serialize(_) => None
+        }
+    }
+
+    /// Where in [`PrefabError::path`] the error was found, if it's known.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            PrefabError::Parse { position, .. }
+            | PrefabError::UnknownType { position, .. }
+            | PrefabError::MissingField { position, .. } => *position,
+            _ => None
+        }
+    }
+
+    pub(crate) fn io(path: &Path, error: io::Error) -> Self {
+        PrefabError::Io {
+            path: path.to_path_buf(),
+            error
+        }
+    }
+
+    pub(crate) fn from_ron(error: ron::error::SpannedError, path: &Path) -> Self {
+        DeserializeErrors::default().ron_error(error, path)
+    }
+}
+
+/// `level.prefab:3:14: ` or `level.prefab: ` without a position.
+fn write_location(f: &mut fmt::Formatter, path: &Path, position: Option<Position>) -> fmt::Result {
+    match position {
+        Some(Position { line, column }) => write!(f, "{}:{line}:{column}: ", path.to_string_lossy()),
+        None => write!(f, "{}: ", path.to_string_lossy())
+    }
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrefabError::Parse { path, position, message } => {
+                write_location(f, path, *position)?;
+                f.write_str(message)
+            }
+            PrefabError::UnknownType { path, position, type_path } => {
+                if let Some(path) = path {
+                    write_location(f, path, *position)?;
+                }
+                write!(f, "`{type_path}` isn't registered")
+            }
+            PrefabError::MissingField { path, position, field } => {
+                write_location(f, path, *position)?;
+                write!(f, "missing field `{field}`")
+            }
+            PrefabError::InvalidEntity { path, entity, reason } => {
+                if let Some(path) = path {
+                    write_location(f, path, None)?;
+                }
+                match entity {
+                    Some(entity) => write!(f, "{entity:?} {reason}"),
+                    None => f.write_str(reason)
+                }
+            }
+            PrefabError::Io { path, error } => write!(f, "{}: {error}", path.to_string_lossy()),
+            PrefabError::Migration { path, message } => write!(f, "{}: {message}", path.to_string_lossy()),
+            PrefabError::Cycle { chain } => {
+                let chain = chain.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>().join(" -> ");
+                write!(f, "prefab contains itself ({chain})")
+            }
+            PrefabError::NotLoaded { path: Some(path) } => write!(f, "{} failed to load", path.to_string_lossy()),
+            PrefabError::NotLoaded { path: None } => f.write_str("prefab isn't loaded"),
+            PrefabError::Spawn { path: Some(path), source } => write!(f, "{}: {source}", path.to_string_lossy()),
+            PrefabError::Spawn { path: None, source } => write!(f, "{source}"),
+            PrefabError::FeatureDisabled { path, feature } => write!(f, "{} can't be read or written without the `{feature}` feature", path.to_string_lossy()),
+            PrefabError::Serialize(message) => f.write_str(message)
+        }
+    }
+}
+
+impl std::error::Error for PrefabError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PrefabError::Io { error, .. } => Some(error),
+            PrefabError::Spawn { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+/// Errors the prefab deserializers fail with that are more than a message.
+#[derive(Debug)]
+enum DeserializeError {
+    UnknownType(String),
+    MissingField(&'static str),
+    Migration(String),
+    /// The document was saved with a format version that has to be migrated first.
+    Outdated(u32),
+}
+
+/// Where the prefab deserializers note what they failed with, which formats only keep as a message.
+///
+/// Shared by every seed deserializing one file, read back once the format returns its error.
+#[derive(Default)]
+pub struct DeserializeErrors(Cell<Option<DeserializeError>>);
+impl DeserializeErrors {
+    fn fail<E: serde::de::Error>(&self, error: DeserializeError, serde_error: E) -> E {
+        self.0.set(Some(error));
+        serde_error
+    }
+
+    /// The error deserializers raise for entries of a type that isn't registered.
+    pub(crate) fn unknown_type<E: serde::de::Error>(&self, type_path: &str) -> E {
+        self.fail(DeserializeError::UnknownType(type_path.to_owned()), E::custom(format_args!("`{type_path}` isn't registered")))
+    }
+
+    /// The error deserializers raise when a saved value of `type_path` couldn't be migrated.
+    pub(crate) fn migration_failed<E: serde::de::Error>(&self, type_path: &str, reason: &str) -> E {
+        let message = format!("couldn't migrate `{type_path}`: {reason}");
+        self.fail(DeserializeError::Migration(message.clone()), E::custom(message))
+    }
+
+    pub(crate) fn missing_field<E: serde::de::Error>(&self, field: &'static str) -> E {
+        self.fail(DeserializeError::MissingField(field), E::missing_field(field))
+    }
+
+    /// Stops deserializing a document of a format `version` that has to be migrated first, see [`crate::format::FormatMigrations`].
+    pub(crate) fn outdated<E: serde::de::Error>(&self, version: u32) -> E {
+        self.fail(DeserializeError::Outdated(version), E::custom(format_args!("format version {version} has to be migrated")))
+    }
+
+    /// The version deserializing stopped at with [`DeserializeErrors::outdated`], if it did.
+    #[cfg(any(feature = "ron", feature = "json"))]
+    pub(crate) fn outdated_version(&self) -> Option<u32> {
+        match self.0.take() {
+            Some(DeserializeError::Outdated(version)) => Some(version),
+            error => {
+                self.0.set(error);
+                None
+            }
+        }
+    }
+
+    /// Turns an error raised while deserializing the file at `path` into the error that was noted for it, or a parse error.
+    pub(crate) fn error(&self, path: &Path, position: Option<Position>, message: String) -> PrefabError {
+        match self.0.take() {
+            Some(DeserializeError::UnknownType(type_path)) => PrefabError::UnknownType {
+                path: Some(path.to_path_buf()),
+                position,
+                type_path
+            },
+            Some(DeserializeError::MissingField(field)) => PrefabError::MissingField {
+                path: path.to_path_buf(),
+                position,
+                field: field.to_owned()
+            },
+            Some(DeserializeError::Migration(message)) => PrefabError::Migration {
+                path: path.to_path_buf(),
+                message
+            },
+            // Only left over in formats that can't be migrated.
+            Some(DeserializeError::Outdated(version)) => PrefabError::Migration {
+                path: path.to_path_buf(),
+                message: format!("format version {version} has to be migrated, which this format doesn't support")
+            },
+            None => PrefabError::Parse {
+                path: path.to_path_buf(),
+                position,
+                message
+            }
+        }
+    }
+
+    pub(crate) fn ron_error(&self, error: ron::error::SpannedError, path: &Path) -> PrefabError {
+        let position = Some(Position {
+            line: error.position.line,
+            column: error.position.col
+        });
+
+        match error.code {
+            ron::Error::MissingStructField { field, .. } => PrefabError::MissingField {
+                path: path.to_path_buf(),
+                position,
+                field: field.to_owned()
+            },
+            code => self.error(path, position, code.to_string())
+        }
+    }
+}
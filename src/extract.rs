@@ -0,0 +1,354 @@
+use std::any::TypeId;
+
+use bevy::{prelude::*, scene::{SceneFilter, DynamicEntity}, reflect::{ReflectMut, TypeRegistryInternal}, ecs::reflect::ReflectMapEntities, utils::{HashSet, HashMap}};
+
+use crate::{Prefab, error::PrefabError, derived::DerivedComponents, lenient::PrefabLoadReport, boundary::{PrefabBoundaryKind, boundary_of}, instance::PrefabInstance, nested::NestedPrefab, overrides::prefab_overrides};
+
+/// Controls what [`extract_prefab`] saves.
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Name given to the extracted prefab.
+    pub name: String,
+    /// Boundary rules used on top of the [`crate::boundary::ReflectPrefabBoundary`] type data in the type registry, taking precedence over it.
+    pub boundary_markers: Vec<(TypeId, PrefabBoundaryKind)>,
+    /// Components saved for regular entities.
+    ///
+    /// Components in the world's [`DerivedComponents`] are never saved, regardless of this filter.
+    pub entity_filter: SceneFilter,
+    /// Components saved for boundary entities.
+    pub boundary_filter: SceneFilter,
+    /// Resources saved alongside the entities.
+    pub resource_filter: SceneFilter,
+    /// How many levels of descendants below the root are saved, `None` for no limit.
+    ///
+    /// Entities at the limit are saved like a [`PrefabBoundaryKind::DropChildren`] boundary.
+    pub max_depth: Option<usize>,
+}
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        let entity_filter = SceneFilter::default();
+
+        let mut boundary_filter = entity_filter.clone();
+        boundary_filter.deny::<Children>();
+
+        Self {
+            name: String::default(),
+            boundary_markers: Vec::default(),
+            entity_filter,
+            boundary_filter,
+            resource_filter: SceneFilter::deny_all(),
+            max_depth: None
+        }
+    }
+}
+impl ExtractOptions {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..default()
+        }
+    }
+
+    pub fn with_boundary<T: Component>(mut self, kind: PrefabBoundaryKind) -> Self {
+        self.boundary_markers.push((TypeId::of::<T>(), kind));
+        self
+    }
+
+    pub fn with_resource_filter(mut self, filter: SceneFilter) -> Self {
+        self.resource_filter = filter;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+}
+
+/// Snapshots `root` and its descendants into a prefab.
+///
+/// Fails if `root` doesn't exist.
+pub fn extract_prefab(
+    world: &World,
+    root: Entity,
+    options: &ExtractOptions
+) -> Result<Prefab, PrefabError> {
+    if world.get_entity(root).is_none() {
+        return Err(PrefabError::InvalidEntity {
+            path: None,
+            entity: Some(root),
+            reason: "doesn't exist".to_owned()
+        });
+    }
+
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+
+    // Same as any entity at the limit, a root at depth 0 is saved without its children.
+    let (mut entities, mut boundaries) = match options.max_depth {
+        Some(0) => (Vec::new(), vec![root]),
+        _ => (vec![root], Vec::new())
+    };
+    let mut nested = Vec::new();
+    let mut skipped = HashSet::new();
+
+    let mut entities_to_check = world.get::<Children>(root).map(|children| children.iter().map(|child| (*child, 1)).collect::<Vec<_>>()).unwrap_or_default();
+
+    while let Some((entity, depth)) = entities_to_check.pop() {
+        let Some(entity_ref) = world.get_entity(entity) else {
+            continue;
+        };
+
+        if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            continue;
+        }
+
+        let boundary = boundary_of(world, entity_ref, &options.boundary_markers, &type_registry)
+            .or_else(|| (options.max_depth == Some(depth)).then_some(PrefabBoundaryKind::DropChildren));
+
+        match boundary {
+            Some(PrefabBoundaryKind::Skip) => {
+                skipped.insert(entity);
+            }
+            Some(PrefabBoundaryKind::NestedPrefab) => match nested_prefab(world, entity) {
+                Some(nested_prefab) => nested.push(nested_prefab),
+                None => {
+                    warn!("{entity:?} is marked as a nested prefab but wasn't spawned from a prefab asset, saving it without its children.");
+                    boundaries.push(entity);
+                }
+            },
+            Some(PrefabBoundaryKind::DropChildren) => boundaries.push(entity),
+            None => {
+                entities.push(entity);
+
+                if let Some(children) = entity_ref.get::<Children>() {
+                    entities_to_check.extend(children.iter().map(|child| (*child, depth + 1)));
+                }
+            }
+        }
+    }
+
+    drop(type_registry);
+
+    // Derived components are added back on spawn, saving them would only duplicate state.
+    let derived_components = world.get_resource::<DerivedComponents>().cloned().unwrap_or_default();
+
+    let mut entity_filter = options.entity_filter.clone();
+    derived_components.deny_derived(&mut entity_filter);
+
+    let mut boundary_filter = options.boundary_filter.clone();
+    derived_components.deny_derived(&mut boundary_filter);
+
+    let mut scene_builder = DynamicSceneBuilder::from_world(world);
+
+    scene_builder
+        .with_filter(entity_filter)
+        .extract_entities(entities.into_iter());
+
+    scene_builder
+        .with_filter(boundary_filter)
+        .extract_entities(boundaries.into_iter());
+
+    // Nested prefabs keep their place in the hierarchy, their contents come from the nested prefab's own file.
+    let mut nested_filter = SceneFilter::deny_all();
+    nested_filter.allow::<Parent>();
+
+    scene_builder
+        .with_filter(nested_filter)
+        .extract_entities(nested.iter().map(|nested| nested.entity));
+
+    scene_builder
+        .with_resource_filter(options.resource_filter.clone())
+        .extract_resources();
+
+    let mut scene = scene_builder.build();
+
+    // The root's parent lives outside of the prefab, keeping it would point the spawned root at a random entity.
+    if let Some(root) = scene.entities.iter_mut().find(|entity| entity.entity == root) {
+        root.components.retain(|component| component.type_name() != std::any::type_name::<Parent>());
+    }
+
+    if !skipped.is_empty() {
+        for entity in &mut scene.entities {
+            remove_children(entity, &skipped);
+        }
+    }
+
+    // Runtime entity ids differ between sessions, save local ones instead so identical hierarchies give identical files.
+    let local_ids = local_entity_ids(world, root, &scene);
+    renumber_entities(&mut scene, &mut nested, &local_ids, &world.resource::<AppTypeRegistry>().read())?;
+
+    Ok(Prefab {
+        name: options.name.clone(),
+        scene,
+        nested,
+        variant: None,
+        bases: Vec::default(),
+        report: PrefabLoadReport::default()
+    })
+}
+
+/// Dense ids for the entities in `scene`, the root is 0 and the rest follow depth-first in [`Children`] order.
+fn local_entity_ids(world: &World, root: Entity, scene: &DynamicScene) -> HashMap<Entity, Entity> {
+    let in_scene = scene.entities.iter().map(|entity| entity.entity).collect::<HashSet<_>>();
+    let mut local_ids = HashMap::default();
+
+    let mut entities_to_visit = vec![root];
+    while let Some(entity) = entities_to_visit.pop() {
+        if !in_scene.contains(&entity) || local_ids.contains_key(&entity) {
+            continue;
+        }
+
+        local_ids.insert(entity, Entity::from_raw(local_ids.len() as u32));
+
+        if let Some(children) = world.get::<Children>(entity) {
+            entities_to_visit.extend(children.iter().rev());
+        }
+    }
+
+    // Everything extracted should be reachable from the root, but make sure nothing ends up without an id.
+    for entity in &scene.entities {
+        if !local_ids.contains_key(&entity.entity) {
+            local_ids.insert(entity.entity, Entity::from_raw(local_ids.len() as u32));
+        }
+    }
+
+    local_ids
+}
+
+/// Replaces every entity id in `scene` & `nested`, including those inside components implementing [`MapEntities`](bevy::ecs::entity::MapEntities), using `local_ids`.
+///
+/// Fails if an entity, a component or a nested prefab references an entity outside of `local_ids`, its id would point at an unrelated entity once spawned.
+pub(crate) fn renumber_entities(scene: &mut DynamicScene, nested: &mut [NestedPrefab], local_ids: &HashMap<Entity, Entity>, type_registry: &TypeRegistryInternal) -> Result<(), PrefabError> {
+    for entity in &mut scene.entities {
+        entity.entity = *local_ids.get(&entity.entity).ok_or_else(|| PrefabError::InvalidEntity {
+            path: None,
+            entity: Some(entity.entity),
+            reason: "isn't part of the prefab".to_owned()
+        })?;
+
+        for component in &mut entity.components {
+            let maps_entities = type_registry.get_with_name(component.type_name())
+                .is_some_and(|registration| registration.data::<ReflectMapEntities>().is_some());
+
+            if maps_entities {
+                map_entity_values(component.as_mut(), local_ids).map_err(|outside| PrefabError::InvalidEntity {
+                    path: None,
+                    entity: Some(outside),
+                    reason: format!("is referenced by `{}` but isn't part of the prefab", component.type_name())
+                })?;
+            }
+        }
+    }
+
+    scene.entities.sort_by_key(|entity| entity.entity.index());
+
+    for nested in nested.iter_mut() {
+        nested.entity = *local_ids.get(&nested.entity).ok_or_else(|| PrefabError::InvalidEntity {
+            path: None,
+            entity: Some(nested.entity),
+            reason: format!("nests {} but isn't in the prefab", nested.path)
+        })?;
+    }
+    nested.sort_by_key(|nested| nested.entity.index());
+
+    Ok(())
+}
+
+/// Maps every [`Entity`] found in `value`, failing with the first one outside of `entity_map`.
+pub(crate) fn map_entity_values(value: &mut dyn Reflect, entity_map: &HashMap<Entity, Entity>) -> Result<(), Entity> {
+    if let Some(entity) = value.downcast_mut::<Entity>() {
+        *entity = *entity_map.get(entity).ok_or(*entity)?;
+
+        return Ok(());
+    }
+
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => {
+            for i in 0..value.field_len() {
+                if let Some(field) = value.field_at_mut(i) {
+                    map_entity_values(field, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::TupleStruct(value) => {
+            for i in 0..value.field_len() {
+                if let Some(field) = value.field_mut(i) {
+                    map_entity_values(field, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::Tuple(value) => {
+            for i in 0..value.field_len() {
+                if let Some(field) = value.field_mut(i) {
+                    map_entity_values(field, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::List(value) => {
+            for i in 0..value.len() {
+                if let Some(item) = value.get_mut(i) {
+                    map_entity_values(item, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::Array(value) => {
+            for i in 0..value.len() {
+                if let Some(item) = value.get_mut(i) {
+                    map_entity_values(item, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::Map(value) => {
+            for i in 0..value.len() {
+                if let Some((_, item)) = value.get_at_mut(i) {
+                    map_entity_values(item, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::Enum(value) => {
+            for i in 0..value.field_len() {
+                if let Some(field) = value.field_at_mut(i) {
+                    map_entity_values(field, entity_map)?;
+                }
+            }
+        }
+        ReflectMut::Value(_) => {}
+    }
+
+    Ok(())
+}
+
+/// A reference to the prefab `entity` was spawned from, along with how it differs from it.
+fn nested_prefab(world: &World, entity: Entity) -> Option<NestedPrefab> {
+    let prefab_instance = world.get::<PrefabInstance>(entity)?;
+    let path = world.resource::<AssetServer>().get_handle_path(&prefab_instance.handle)?;
+
+    Some(NestedPrefab {
+        entity,
+        path: path.path().to_string_lossy().into_owned(),
+        handle: prefab_instance.handle.clone(),
+        overrides: prefab_overrides(world, entity)
+    })
+}
+
+/// Removes `removed` from the [`Children`] saved for `entity`, so they aren't mapped to new entities on spawn.
+pub(crate) fn remove_children(entity: &mut DynamicEntity, removed: &HashSet<Entity>) {
+    let Some(children) = entity.components.iter_mut().find(|component| component.type_name() == std::any::type_name::<Children>()) else {
+        return;
+    };
+    let ReflectMut::TupleStruct(children) = children.reflect_mut() else {
+        return;
+    };
+    let Some(ReflectMut::List(children)) = children.field_mut(0).map(|field| field.reflect_mut()) else {
+        return;
+    };
+
+    let mut i = 0;
+    while i < children.len() {
+        if children.get(i).and_then(|child| child.downcast_ref::<Entity>()).is_some_and(|child| removed.contains(child)) {
+            children.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
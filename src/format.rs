@@ -0,0 +1,279 @@
+use std::{path::Path, sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard}};
+
+use bevy::prelude::Resource;
+use serde::{Deserialize, de::IgnoredAny};
+
+use crate::{PREFAB_STRUCT, error::PrefabError, tree::PrefabLayout};
+#[cfg(any(feature = "ron", feature = "json"))]
+use {bevy::reflect::TypeRegistryArc, serde::de::DeserializeSeed, crate::{Prefab, PrefabDeserializer, error::DeserializeErrors, lenient::PrefabLoadMode}};
+#[cfg(feature = "json")]
+use crate::json::json_error;
+#[cfg(feature = "cli")]
+use crate::deserialize_prefab;
+#[cfg(any(feature = "cli", feature = "save-game"))]
+use crate::serialize_prefab_as;
+#[cfg(all(any(feature = "cli", feature = "save-game"), feature = "binary"))]
+use crate::binary::serialize_binary_prefab;
+#[cfg(all(any(feature = "cli", feature = "save-game"), feature = "json"))]
+use crate::json::serialize_json_prefab;
+#[cfg(any(feature = "cli", feature = "save-game"))]
+use std::{fs, path::PathBuf};
+
+/// Encodings prefab files can be stored in, told apart by their extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefabFormat {
+    /// Human readable `.prefab` files.
+    Ron,
+    /// Compact `.prefab.bin` files for shipping builds.
+    Binary,
+    /// `.prefab.json` files, for tools that don't speak RON.
+    Json,
+}
+impl PrefabFormat {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            PrefabFormat::Ron => "prefab",
+            PrefabFormat::Binary => "prefab.bin",
+            PrefabFormat::Json => "prefab.json"
+        }
+    }
+
+    /// The cargo feature that has to be enabled to read & write this format.
+    pub const fn feature(self) -> &'static str {
+        match self {
+            PrefabFormat::Ron => "ron",
+            PrefabFormat::Binary => "binary",
+            PrefabFormat::Json => "json"
+        }
+    }
+
+    pub(crate) fn disabled_error(self, path: &Path) -> PrefabError {
+        PrefabError::FeatureDisabled {
+            path: path.to_path_buf(),
+            feature: self.feature()
+        }
+    }
+
+    /// The format of the file at `path`, files that don't have any of the other extensions are read as RON.
+    pub fn from_path(path: &Path) -> Self {
+        let file_name = path.file_name().map(|file_name| file_name.to_string_lossy()).unwrap_or_default();
+
+        [PrefabFormat::Binary, PrefabFormat::Json].into_iter()
+            .find(|format| file_name.ends_with(&format!(".{}", format.extension())))
+            .unwrap_or(PrefabFormat::Ron)
+    }
+}
+
+/// Version of the prefab format written by this crate.
+///
+/// Version 0 files predate the `version` field & need no changes to load as version 1.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Rewrites a whole prefab document saved with an older format version into the next one.
+pub type FormatMigration = fn(&mut ron::Value) -> Result<(), String>;
+
+/// Steps taking documents from the version they're added with to the next version.
+///
+/// Documents that need any of these are migrated as a [`ron::Value`], which doesn't keep enum variant names, so loading them fails for components holding enums until they're fixed by hand.
+/// Shared with the prefab loaders like [`bevy::prelude::AppTypeRegistry`], so steps added through the resource apply to every prefab loaded afterwards.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FormatMigrations(Arc<RwLock<Vec<(u32, FormatMigration)>>>);
+impl FormatMigrations {
+    /// Adds the step taking documents of version `from` to `from + 1`.
+    pub fn add(&mut self, from: u32, migration: FormatMigration) -> &mut Self {
+        let mut steps = self.steps_mut();
+        steps.push((from, migration));
+        steps.sort_by_key(|(from, _)| *from);
+        drop(steps);
+
+        self
+    }
+
+    /// Whether a document of `version` has to be migrated before it can be loaded.
+    pub fn needs_migration(&self, version: u32) -> bool {
+        self.steps().iter().any(|(from, _)| *from >= version)
+    }
+
+    fn steps(&self) -> RwLockReadGuard<'_, Vec<(u32, FormatMigration)>> {
+        self.0.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn steps_mut(&self) -> RwLockWriteGuard<'_, Vec<(u32, FormatMigration)>> {
+        self.0.write().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Runs every migration step from `version` to [`FORMAT_VERSION`] on the document in `bytes`.
+    pub fn migrate_document(&self, bytes: &[u8], version: u32, path: &Path) -> Result<ron::Value, PrefabError> {
+        let mut document = read_document::<ron::Value>(bytes, path)?;
+
+        for (from, migration) in self.steps().iter().filter(|(from, _)| *from >= version) {
+            migration(&mut document).map_err(|err| PrefabError::Migration {
+                path: path.to_path_buf(),
+                message: format!("migrating from format version {from}: {err}")
+            })?;
+        }
+
+        Ok(document)
+    }
+
+    /// The version a document that failed to load with `errors` has to be migrated from, if that's why it failed.
+    ///
+    /// Loads stop at a `version` that needs migrating. Documents without one are only read again for it when version 0 needs migrating.
+    #[cfg(any(feature = "ron", feature = "json"))]
+    pub(crate) fn outdated_version(&self, errors: &DeserializeErrors, bytes: &[u8], path: &Path) -> Option<u32> {
+        errors.outdated_version().or_else(|| {
+            let version = self.needs_migration(0).then(|| read_header(bytes, path).ok()).flatten()?.0;
+            self.needs_migration(version).then_some(version)
+        })
+    }
+}
+
+/// The fields of a prefab file needed to tell how it's written, skipping everything else.
+#[derive(Deserialize)]
+#[serde(rename = "Prefab")]
+struct PrefabHeader {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    root: Option<IgnoredAny>,
+}
+
+/// Reads a document in one of the self-describing formats.
+fn read_document<T: serde::de::DeserializeOwned>(bytes: &[u8], path: &Path) -> Result<T, PrefabError> {
+    match PrefabFormat::from_path(path) {
+        PrefabFormat::Ron => ron::de::from_bytes(bytes).map_err(|err| PrefabError::from_ron(err, path)),
+        #[cfg(feature = "json")]
+        PrefabFormat::Json => serde_json::from_slice(bytes).map_err(|err| json_error(err, path, &DeserializeErrors::default())),
+        PrefabFormat::Binary => Err(PrefabError::Migration {
+            path: path.to_path_buf(),
+            message: "binary prefabs are always written in the current version & can't be migrated".to_owned()
+        }),
+        #[allow(unreachable_patterns)]
+        format => Err(format.disabled_error(path))
+    }
+}
+
+/// The format version of a RON or JSON prefab file & the layout of its entities.
+pub fn read_header(bytes: &[u8], path: &Path) -> Result<(u32, PrefabLayout), PrefabError> {
+    let header = read_document::<PrefabHeader>(bytes, path)?;
+
+    let layout = match header.root {
+        Some(_) => PrefabLayout::Tree,
+        None => PrefabLayout::Flat
+    };
+
+    Ok((header.version, layout))
+}
+
+/// Migrates the RON or JSON document in `bytes` from `version` & loads it.
+#[cfg(any(feature = "ron", feature = "json"))]
+pub(crate) fn deserialize_migrated(bytes: &[u8], path: &Path, version: u32, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let document = migrations.migrate_document(bytes, version, path)?;
+
+    // Migrated documents are loaded as they are, whichever version they still claim.
+    let prefab_deserializer = PrefabDeserializer {
+        type_registry,
+        mode,
+        migrations: &FormatMigrations::default(),
+        errors: &DeserializeErrors::default()
+    };
+
+    // The migrated document no longer has positions to point at.
+    prefab_deserializer.deserialize(document).map_err(|err| PrefabError::Migration {
+        path: path.to_path_buf(),
+        message: format!("{err} after migrating from format version {version}")
+    })
+}
+
+/// Checks that `version` can be loaded, files from a newer build can't.
+pub fn check_version(version: u32) -> Result<(), String> {
+    match version > FORMAT_VERSION {
+        true => Err(format!("{PREFAB_STRUCT} format version {version} is newer than the supported version {FORMAT_VERSION}")),
+        false => Ok(())
+    }
+}
+
+/// Reads the prefab file at `input` & writes it to `output`, in the formats their extensions ask for.
+///
+/// Variants stay variants, their bases aren't read.
+#[cfg(feature = "cli")]
+pub fn convert_prefab_file(input: &Path, output: &Path, type_registry: &TypeRegistryArc, migrations: &FormatMigrations) -> Result<(), PrefabError> {
+    let bytes = fs::read(input).map_err(|err| PrefabError::io(input, err))?;
+    let prefab = deserialize_prefab(&bytes, input, type_registry, PrefabLoadMode::Strict, migrations)?;
+
+    let converted = serialize_prefab_file(&prefab, output, PrefabLayout::Flat, type_registry)?;
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|err| PrefabError::io(parent, err))?;
+    }
+    fs::write(output, converted).map_err(|err| PrefabError::io(output, err))?;
+
+    Ok(())
+}
+
+/// Rewrites the RON or JSON prefab at `path` in the current format version, keeping its layout.
+///
+/// Returns `false` if the file was already up to date.
+#[cfg(feature = "cli")]
+pub fn upgrade_prefab_file(path: &Path, type_registry: &TypeRegistryArc, migrations: &FormatMigrations) -> Result<bool, PrefabError> {
+    let bytes = fs::read(path).map_err(|err| PrefabError::io(path, err))?;
+    let (version, layout) = read_header(&bytes, path)?;
+
+    if version == FORMAT_VERSION {
+        return Ok(false);
+    }
+
+    let prefab = deserialize_prefab(&bytes, path, type_registry, PrefabLoadMode::Strict, migrations)?;
+    let serialized_prefab = serialize_prefab_file(&prefab, path, layout, type_registry)?;
+
+    replace_file(path, &serialized_prefab)?;
+
+    Ok(true)
+}
+
+/// Serializes `prefab` canonically, in the format `path` asks for & in `layout` where that format supports it.
+#[cfg(any(feature = "cli", feature = "save-game"))]
+pub fn serialize_prefab_file(prefab: &Prefab, path: &Path, layout: PrefabLayout, type_registry: &TypeRegistryArc) -> Result<Vec<u8>, PrefabError> {
+    match PrefabFormat::from_path(path) {
+        PrefabFormat::Ron => serialize_prefab_as(prefab, type_registry, layout).map(String::into_bytes).map_err(serialize_error),
+        #[cfg(feature = "binary")]
+        PrefabFormat::Binary => serialize_binary_prefab(prefab, type_registry).map_err(serialize_error),
+        #[cfg(feature = "json")]
+        PrefabFormat::Json => serialize_json_prefab(prefab, type_registry, layout).map(String::into_bytes).map_err(serialize_error),
+        #[allow(unreachable_patterns)]
+        format => Err(format.disabled_error(path))
+    }
+}
+
+/// Writes `contents` next to `path` & renames it over the file, so a failed write never leaves half a prefab behind.
+///
+/// The temporary file is removed again if either step fails.
+#[cfg(any(feature = "cli", feature = "save-game"))]
+pub(crate) fn replace_file(path: &Path, contents: &[u8]) -> Result<(), PrefabError> {
+    let temp_path = append_extension(path, "tmp");
+
+    let result = fs::write(&temp_path, contents)
+        .map_err(|err| PrefabError::io(&temp_path, err))
+        .and_then(|()| fs::rename(&temp_path, path).map_err(|err| PrefabError::io(path, err)));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+#[cfg(any(feature = "cli", feature = "save-game"))]
+pub(crate) fn serialize_error(err: impl std::fmt::Display) -> PrefabError {
+    PrefabError::Serialize(err.to_string())
+}
+
+/// `goblin.prefab` + `bak` -> `goblin.prefab.bak`
+#[cfg(any(feature = "cli", feature = "save-game"))]
+pub(crate) fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+
+    path.into()
+}
@@ -0,0 +1,46 @@
+use std::any::TypeId;
+
+use bevy::{prelude::*, ecs::entity::EntityMap, scene::DynamicEntity, reflect::TypeRegistryInternal, utils::HashMap};
+
+use crate::Prefab;
+
+/// Placed on the root of a spawned prefab instance.
+#[derive(Component)]
+pub struct PrefabInstance {
+    pub handle: Handle<Prefab>,
+    /// Maps entities in [`Prefab::scene`] to the entities spawned for them.
+    pub entity_map: EntityMap,
+    /// Component types written to each entity in [`Prefab::scene`], so ones later removed from the file can be taken off again.
+    pub components: HashMap<Entity, Vec<TypeId>>,
+}
+impl PrefabInstance {
+    /// Returns the entity in [`Prefab::scene`] that `entity` was spawned from.
+    pub fn source_of(&self, entity: Entity) -> Option<Entity> {
+        self.entity_map.iter().find(|(_, spawned)| *spawned == entity).map(|(source, _)| source)
+    }
+}
+
+/// Placed on every entity spawned from a prefab, including the instance root.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefabSource {
+    /// The root entity holding the [`PrefabInstance`].
+    pub instance: Entity,
+    /// The entity in [`Prefab::scene`] this entity was spawned from.
+    pub source: Entity,
+}
+
+/// Looks up which prefab and which entity in it `entity` was spawned from.
+pub fn prefab_source_of(world: &World, entity: Entity) -> Option<(Handle<Prefab>, Entity)> {
+    let source = world.get::<PrefabSource>(entity)?;
+    let instance = world.get::<PrefabInstance>(source.instance)?;
+
+    Some((instance.handle.clone(), source.source))
+}
+
+/// Registered component types of `entity`, in the order they're stored.
+pub(crate) fn component_types(entity: &DynamicEntity, type_registry: &TypeRegistryInternal) -> Vec<TypeId> {
+    entity.components.iter()
+        .filter_map(|component| type_registry.get_with_name(component.type_name()))
+        .map(|registration| registration.type_id())
+        .collect()
+}
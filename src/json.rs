@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use bevy::{prelude::*, asset::{AssetLoader, LoadContext}, reflect::TypeRegistryArc, utils::BoxedFuture};
+use serde::de::DeserializeSeed;
+
+use crate::{Prefab, PrefabSerializer, PrefabDeserializer, load_prefab, error::{PrefabError, Position, DeserializeErrors}, format::{self, PrefabFormat, FormatMigrations}, lenient::{PrefabLoadMode, PrefabLoaderSettings}, tree::PrefabLayout};
+
+/// Loads `.prefab.json` files, the JSON flavour of `.prefab` files.
+#[derive(Debug)]
+pub struct JsonPrefabLoader {
+    type_registry: TypeRegistryArc,
+    settings: PrefabLoaderSettings,
+    migrations: FormatMigrations,
+}
+impl FromWorld for JsonPrefabLoader {
+    fn from_world(world: &mut World) -> Self {
+        let type_registry = world.resource::<AppTypeRegistry>();
+
+        JsonPrefabLoader {
+            type_registry: type_registry.0.clone(),
+            settings: world.get_resource::<PrefabLoaderSettings>().cloned().unwrap_or_default(),
+            migrations: world.get_resource::<FormatMigrations>().cloned().unwrap_or_default()
+        }
+    }
+}
+impl AssetLoader for JsonPrefabLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(load_prefab(bytes, load_context, &self.type_registry, self.settings.mode(), &self.migrations))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        const EXTENSIONS: &[&str] = &[PrefabFormat::Json.extension()];
+        EXTENSIONS
+    }
+}
+
+/// Moves the position serde_json appends to its messages into the [`PrefabError`], same as RON errors.
+pub(crate) fn json_error(err: serde_json::Error, path: &Path, errors: &DeserializeErrors) -> PrefabError {
+    let message = err.to_string();
+    let suffix = format!(" at line {} column {}", err.line(), err.column());
+    let message = message.strip_suffix(&suffix).unwrap_or(&message).to_owned();
+
+    let position = Position {
+        line: err.line(),
+        column: err.column()
+    };
+
+    errors.error(path, Some(position), message)
+}
+
+pub fn deserialize_json_prefab(bytes: &[u8], path: &Path, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let errors = DeserializeErrors::default();
+    let prefab_deserializer = PrefabDeserializer {
+        type_registry,
+        mode,
+        migrations,
+        errors: &errors
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+
+    let prefab = match prefab_deserializer.deserialize(&mut deserializer) {
+        Ok(prefab) => prefab,
+        Err(err) => return match migrations.outdated_version(&errors, bytes, path) {
+            Some(version) => format::deserialize_migrated(bytes, path, version, type_registry, mode, migrations),
+            None => Err(json_error(err, path, &errors))
+        }
+    };
+    deserializer.end().map_err(|e| json_error(e, path, &errors))?;
+
+    Ok(prefab)
+}
+
+/// Serializes `prefab` into the pretty-printed JSON stored in `.prefab.json` files.
+pub fn serialize_json_prefab(prefab: &Prefab, registry: &TypeRegistryArc, layout: PrefabLayout) -> Result<String, serde_json::Error> {
+    let prefab_serializer = PrefabSerializer {
+        prefab,
+        registry,
+        canonical: true,
+        layout
+    };
+
+    serde_json::to_string_pretty(&prefab_serializer)
+}
@@ -0,0 +1,641 @@
+use std::{any::TypeId, path::PathBuf, sync::{Arc, RwLock}};
+
+use bevy::{prelude::*, reflect::{TypeInfo, TypeRegistration, TypeRegistryInternal, VariantInfo, ReflectDeserialize, serde::{TypedReflectDeserializer, UntypedReflectDeserializer}}, scene::{DynamicEntity, serde::{ENTITY_STRUCT, ENTITY_FIELD_COMPONENTS}}, utils::HashSet};
+use serde::{Deserialize, de::{DeserializeSeed, IgnoredAny, IntoDeserializer, Visitor, MapAccess, SeqAccess, value::{self, MapDeserializer, SeqDeserializer}}};
+
+use crate::{error::DeserializeErrors, migration::{ReflectPrefabMigrations, registration_for, split_version}};
+
+/// What the loader does with components it can't deserialize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefabLoadMode {
+    /// Fail the whole prefab, like a [`DynamicScene`] would.
+    #[default]
+    Strict,
+    /// Skip the component & record it in the prefab's [`PrefabLoadReport`]. Meant for mods & refactors, where some types may be missing.
+    ///
+    /// Values that don't fit their type are skipped too, unless the type holds an enum, those still fail the prefab.
+    ///
+    /// Only possible in self-describing formats, others are always loaded strictly.
+    Lenient,
+}
+
+/// How the prefab loaders read files.
+///
+/// Shared with the loaders like [`AppTypeRegistry`], so changes made through the resource apply to every prefab loaded afterwards.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PrefabLoaderSettings {
+    mode: Arc<RwLock<PrefabLoadMode>>,
+}
+impl PrefabLoaderSettings {
+    pub fn new(mode: PrefabLoadMode) -> Self {
+        Self {
+            mode: Arc::new(RwLock::new(mode))
+        }
+    }
+
+    pub fn mode(&self) -> PrefabLoadMode {
+        *self.mode.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn set_mode(&mut self, mode: PrefabLoadMode) {
+        *self.mode.write().unwrap_or_else(|err| err.into_inner()) = mode;
+    }
+}
+
+/// A component or resource that was left out when loading a prefab in [`PrefabLoadMode::Lenient`].
+#[derive(Debug, Clone)]
+pub struct SkippedComponent {
+    /// File the component was in, which may be a base of the loaded prefab.
+    pub path: PathBuf,
+    /// Entity the component was on, `None` for resources.
+    pub entity: Option<Entity>,
+    pub type_path: String,
+    pub reason: String,
+}
+
+/// Everything that was skipped while loading a prefab.
+#[derive(Debug, Clone, Default)]
+pub struct PrefabLoadReport {
+    pub skipped: Vec<SkippedComponent>,
+}
+impl PrefabLoadReport {
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+fn skipped(entity: Option<Entity>, type_path: String, reason: String) -> SkippedComponent {
+    SkippedComponent {
+        path: PathBuf::default(),
+        entity,
+        type_path,
+        reason
+    }
+}
+
+/// Why a value of `registration`'s type can't be deserialized, if it can't.
+///
+/// Types are deserialized through [`ReflectDeserialize`], or field by field from their type info, in which case every field type needs to be registered as well.
+fn undeserializable_reason(registration: &TypeRegistration, type_registry: &TypeRegistryInternal, visited: &mut HashSet<TypeId>) -> Option<String> {
+    if registration.data::<ReflectDeserialize>().is_some() || !visited.insert(registration.type_id()) {
+        return None;
+    }
+
+    let field_types = match registration.type_info() {
+        TypeInfo::Struct(info) => info.iter().map(|field| field.type_id()).collect::<Vec<_>>(),
+        TypeInfo::TupleStruct(info) => info.iter().map(|field| field.type_id()).collect(),
+        TypeInfo::Tuple(info) => info.iter().map(|field| field.type_id()).collect(),
+        TypeInfo::List(info) => vec![info.item_type_id()],
+        TypeInfo::Array(info) => vec![info.item_type_id()],
+        TypeInfo::Map(info) => vec![info.key_type_id(), info.value_type_id()],
+        TypeInfo::Enum(info) => info.iter().flat_map(|variant| match variant {
+            VariantInfo::Struct(variant) => variant.iter().map(|field| field.type_id()).collect::<Vec<_>>(),
+            VariantInfo::Tuple(variant) => variant.iter().map(|field| field.type_id()).collect(),
+            VariantInfo::Unit(_) => Vec::new()
+        }).collect(),
+        TypeInfo::Value(info) => return Some(format!("`{}` isn't registered with `ReflectDeserialize`", info.type_name()))
+    };
+
+    field_types.into_iter().find_map(|type_id| match type_registry.get(type_id) {
+        Some(field_registration) => undeserializable_reason(field_registration, type_registry, visited),
+        None => Some(format!("a field of `{}` isn't registered", registration.type_name()))
+    })
+}
+
+/// Whether a value of `registration`'s type holds an enum other than [`Option`].
+///
+/// [`BufferedValue`]s lose the names of enum variants, so those values can't be buffered before they're deserialized.
+fn contains_enum(registration: &TypeRegistration, type_registry: &TypeRegistryInternal, visited: &mut HashSet<TypeId>) -> bool {
+    if registration.data::<ReflectDeserialize>().is_some() || !visited.insert(registration.type_id()) {
+        return false;
+    }
+
+    let field_types = match registration.type_info() {
+        TypeInfo::Struct(info) => info.iter().map(|field| field.type_id()).collect::<Vec<_>>(),
+        TypeInfo::TupleStruct(info) => info.iter().map(|field| field.type_id()).collect(),
+        TypeInfo::Tuple(info) => info.iter().map(|field| field.type_id()).collect(),
+        TypeInfo::List(info) => vec![info.item_type_id()],
+        TypeInfo::Array(info) => vec![info.item_type_id()],
+        TypeInfo::Map(info) => vec![info.key_type_id(), info.value_type_id()],
+        TypeInfo::Enum(info) if info.type_name().starts_with("core::option::Option<") => info.iter().flat_map(|variant| match variant {
+            VariantInfo::Tuple(variant) => variant.iter().map(|field| field.type_id()).collect::<Vec<_>>(),
+            _ => Vec::new()
+        }).collect(),
+        TypeInfo::Enum(_) => return true,
+        TypeInfo::Value(_) => return false
+    };
+
+    field_types.into_iter().any(|type_id| type_registry.get(type_id).is_some_and(|field_registration| contains_enum(field_registration, type_registry, visited)))
+}
+
+/// Same as [`bevy::scene::serde::SceneMapDeserializer`], but skips entries that can't be loaded in [`PrefabLoadMode::Lenient`].
+///
+/// Keys may be aliases of a type & carry the version the value was saved with, older values are migrated, see [`crate::migration`].
+///
+/// Gives the deserialized entries along with the skipped ones.
+pub struct ComponentsDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+    pub mode: PrefabLoadMode,
+    /// Entries are resources instead of components.
+    pub resources: bool,
+    /// Entity the components belong to, for the report.
+    pub entity: Option<Entity>,
+    pub errors: &'a DeserializeErrors,
+}
+impl<'a, 'de> DeserializeSeed<'de> for ComponentsDeserializer<'a> {
+    type Value = (Vec<Box<dyn Reflect>>, Vec<SkippedComponent>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+impl<'a> ComponentsDeserializer<'a> {
+    /// Why the entry of type `registration` should be skipped, if it should.
+    fn skip_reason(&self, registration: &TypeRegistration) -> Option<String> {
+        if self.resources && registration.data::<ReflectResource>().is_none() {
+            return Some("isn't registered as a resource".to_owned());
+        }
+        if !self.resources && registration.data::<ReflectComponent>().is_none() {
+            return Some("isn't registered as a component".to_owned());
+        }
+
+        undeserializable_reason(registration, self.type_registry, &mut HashSet::new())
+    }
+}
+
+/// Why a value saved with `version` can't be migrated to the current version of `registration`, if it can't.
+fn version_reason(registration: &TypeRegistration, version: u32) -> Option<String> {
+    let current = registration.data::<ReflectPrefabMigrations>().map(|migrations| migrations.version()).unwrap_or_default();
+
+    (version > current).then(|| format!("saved with version {version}, newer than the current version {current}"))
+}
+
+/// Migrates a value saved with an older `version` & deserializes it as the current version of `registration`.
+fn migrate_value(value: ron::Value, version: u32, migrations: &ReflectPrefabMigrations, registration: &TypeRegistration, type_registry: &TypeRegistryInternal) -> Result<Box<dyn Reflect>, String> {
+    let value = migrations.migrate(value, version)?;
+
+    TypedReflectDeserializer::new(registration, type_registry)
+        .deserialize(value)
+        .map_err(|err| format!("migrated value doesn't match the current version: {err}"))
+}
+impl<'a, 'de> Visitor<'de> for ComponentsDeserializer<'a> {
+    type Value = (Vec<Box<dyn Reflect>>, Vec<SkippedComponent>);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("map of reflect types")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        // Entries of non-self-describing formats can't be skipped, they're always loaded strictly.
+        let mut entries = Vec::new();
+        while let Some(entry) = seq.next_element_seed(UntypedReflectDeserializer::new(self.type_registry))? {
+            entries.push(entry);
+        }
+
+        Ok((entries, Vec::new()))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut entries = Vec::new();
+        let mut skipped_entries = Vec::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let (type_path, version) = split_version(&key).map_err(serde::de::Error::custom)?;
+            let registration = registration_for(self.type_registry, type_path);
+
+            let skip_reason = match (registration, self.mode) {
+                (None, PrefabLoadMode::Strict) => return Err(self.errors.unknown_type(type_path)),
+                (None, PrefabLoadMode::Lenient) => Some("isn't registered".to_owned()),
+                (Some(registration), PrefabLoadMode::Strict) => match version_reason(registration, version) {
+                    Some(reason) => return Err(self.errors.migration_failed(type_path, &reason)),
+                    None => None
+                },
+                (Some(registration), PrefabLoadMode::Lenient) => version_reason(registration, version).or_else(|| self.skip_reason(registration))
+            };
+
+            let migrations = registration
+                .and_then(|registration| registration.data::<ReflectPrefabMigrations>())
+                .filter(|migrations| version < migrations.version());
+
+            match (registration, skip_reason, migrations) {
+                (Some(registration), None, None) if self.mode == PrefabLoadMode::Lenient && !contains_enum(registration, self.type_registry, &mut HashSet::new()) => {
+                    // Buffered first, so a value that doesn't fit its type only skips this entry instead of failing the whole prefab.
+                    let value = map.next_value::<BufferedValue>()?;
+
+                    match TypedReflectDeserializer::new(registration, self.type_registry).deserialize(value) {
+                        Ok(entry) => entries.push(entry),
+                        Err(err) => skipped_entries.push(skipped(self.entity, type_path.to_owned(), err.to_string()))
+                    }
+                }
+                (Some(registration), None, None) => {
+                    entries.push(map.next_value_seed(TypedReflectDeserializer::new(registration, self.type_registry))?);
+                }
+                (Some(registration), None, Some(migrations)) => {
+                    // Old layouts don't fit the current type, so they're read untyped & migrated before becoming a reflect value.
+                    let value = map.next_value::<ron::Value>()?;
+
+                    match (migrate_value(value, version, migrations, registration, self.type_registry), self.mode) {
+                        (Ok(entry), _) => entries.push(entry),
+                        (Err(reason), PrefabLoadMode::Lenient) => skipped_entries.push(skipped(self.entity, type_path.to_owned(), reason)),
+                        (Err(reason), PrefabLoadMode::Strict) => return Err(self.errors.migration_failed(type_path, &reason))
+                    }
+                }
+                (_, reason, _) => {
+                    map.next_value::<IgnoredAny>()?;
+                    skipped_entries.push(skipped(self.entity, type_path.to_owned(), reason.unwrap_or_default()));
+                }
+            }
+        }
+
+        Ok((entries, skipped_entries))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum EntityField {
+    Components,
+}
+
+/// Same as [`bevy::scene::serde::SceneEntitiesDeserializer`], using [`ComponentsDeserializer`] for each entity's components.
+pub struct EntitiesDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+    pub mode: PrefabLoadMode,
+    pub errors: &'a DeserializeErrors,
+}
+impl<'a, 'de> DeserializeSeed<'de> for EntitiesDeserializer<'a> {
+    type Value = (Vec<DynamicEntity>, Vec<SkippedComponent>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for EntitiesDeserializer<'a> {
+    type Value = (Vec<DynamicEntity>, Vec<SkippedComponent>);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("map of entities")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut entities = Vec::new();
+        let mut skipped_entries = Vec::new();
+
+        while let Some(entity) = map.next_key::<Entity>()? {
+            let (components, skipped_components) = map.next_value_seed(EntityDeserializer {
+                components: ComponentsDeserializer {
+                    type_registry: self.type_registry,
+                    mode: self.mode,
+                    resources: false,
+                    entity: Some(entity),
+                    errors: self.errors
+                }
+            })?;
+
+            entities.push(DynamicEntity {
+                entity,
+                components
+            });
+            skipped_entries.extend(skipped_components);
+        }
+
+        Ok((entities, skipped_entries))
+    }
+}
+
+struct EntityDeserializer<'a> {
+    components: ComponentsDeserializer<'a>,
+}
+impl<'a, 'de> DeserializeSeed<'de> for EntityDeserializer<'a> {
+    type Value = (Vec<Box<dyn Reflect>>, Vec<SkippedComponent>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(ENTITY_STRUCT, &[ENTITY_FIELD_COMPONENTS], self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for EntityDeserializer<'a> {
+    type Value = (Vec<Box<dyn Reflect>>, Vec<SkippedComponent>);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let errors = self.components.errors;
+        seq.next_element_seed(self.components)?.ok_or_else(|| errors.missing_field(ENTITY_FIELD_COMPONENTS))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut components = None;
+        let errors = self.components.errors;
+        let mut seed = Some(self.components);
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                EntityField::Components => {
+                    let Some(seed) = seed.take() else {
+                        return Err(serde::de::Error::duplicate_field(ENTITY_FIELD_COMPONENTS));
+                    };
+
+                    components = Some(map.next_value_seed(seed)?);
+                }
+            }
+        }
+
+        components.ok_or_else(|| errors.missing_field(ENTITY_FIELD_COMPONENTS))
+    }
+}
+
+/// A value read as whatever the format says it is, to be deserialized as its actual type afterwards.
+///
+/// Unlike [`ron::Value`] this keeps what the format reported as is, and accepts the same inputs the format itself would for the type asking for it:
+/// `()` for structs without fields, `null` for `None` & numbers written as strings, which JSON does for map keys.
+/// Enum variant names are still lost, formats don't report them to [`serde::Deserializer::deserialize_any`].
+enum BufferedValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    Unit,
+    None,
+    Some(Box<BufferedValue>),
+    Newtype(Box<BufferedValue>),
+    Seq(Vec<BufferedValue>),
+    Map(Vec<(BufferedValue, BufferedValue)>),
+}
+impl<'de> Deserialize<'de> for BufferedValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BufferedValueVisitor)
+    }
+}
+
+struct BufferedValueVisitor;
+impl<'de> Visitor<'de> for BufferedValueVisitor {
+    type Value = BufferedValue;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: serde::de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(BufferedValue::Bool(v))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(BufferedValue::I64(v))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(BufferedValue::U64(v))
+    }
+
+    fn visit_i128<E: serde::de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(BufferedValue::I128(v))
+    }
+
+    fn visit_u128<E: serde::de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(BufferedValue::U128(v))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(BufferedValue::F64(v))
+    }
+
+    fn visit_char<E: serde::de::Error>(self, v: char) -> Result<Self::Value, E> {
+        Ok(BufferedValue::Char(v))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(BufferedValue::String(v.to_owned()))
+    }
+
+    fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(BufferedValue::String(v))
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(BufferedValue::Bytes(v.to_owned()))
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(BufferedValue::Bytes(v))
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(BufferedValue::Unit)
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(BufferedValue::None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        BufferedValue::deserialize(deserializer).map(|value| BufferedValue::Some(Box::new(value)))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        BufferedValue::deserialize(deserializer).map(|value| BufferedValue::Newtype(Box::new(value)))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(BufferedValue::Seq(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+
+        Ok(BufferedValue::Map(entries))
+    }
+}
+
+impl BufferedValue {
+    fn visit_seq<'de, V: Visitor<'de>>(values: Vec<BufferedValue>, visitor: V) -> Result<V::Value, value::Error> {
+        let mut seq = SeqDeserializer::new(values.into_iter());
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+
+        Ok(value)
+    }
+
+    fn visit_map<'de, V: Visitor<'de>>(entries: Vec<(BufferedValue, BufferedValue)>, visitor: V) -> Result<V::Value, value::Error> {
+        let mut map = MapDeserializer::new(entries.into_iter());
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+
+        Ok(value)
+    }
+}
+
+/// Numbers asked for from a string are parsed from it, anything else is read as is.
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident: $number:ty),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self {
+                    BufferedValue::String(string) => match string.parse::<$number>() {
+                        Ok(number) => visitor.$visit(number),
+                        Err(_) => visitor.visit_string(string)
+                    },
+                    value => value.deserialize_any(visitor)
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for BufferedValue {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            BufferedValue::Bool(v) => visitor.visit_bool(v),
+            BufferedValue::I64(v) => visitor.visit_i64(v),
+            BufferedValue::U64(v) => visitor.visit_u64(v),
+            BufferedValue::I128(v) => visitor.visit_i128(v),
+            BufferedValue::U128(v) => visitor.visit_u128(v),
+            BufferedValue::F64(v) => visitor.visit_f64(v),
+            BufferedValue::Char(v) => visitor.visit_char(v),
+            BufferedValue::String(v) => visitor.visit_string(v),
+            BufferedValue::Bytes(v) => visitor.visit_byte_buf(v),
+            BufferedValue::Unit => visitor.visit_unit(),
+            BufferedValue::None => visitor.visit_none(),
+            BufferedValue::Some(v) => visitor.visit_some(*v),
+            BufferedValue::Newtype(v) => visitor.visit_newtype_struct(*v),
+            BufferedValue::Seq(values) => BufferedValue::visit_seq(values, visitor),
+            BufferedValue::Map(entries) => BufferedValue::visit_map(entries, visitor)
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            // JSON writes `None` as `null`, which formats report as unit.
+            BufferedValue::None | BufferedValue::Unit => visitor.visit_none(),
+            BufferedValue::Some(value) => visitor.visit_some(*value),
+            value => visitor.visit_some(value)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            BufferedValue::Newtype(value) => visitor.visit_newtype_struct(*value),
+            value => visitor.visit_newtype_struct(value)
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            // RON writes structs without fields as `()`.
+            BufferedValue::Unit => BufferedValue::visit_map(Vec::new(), visitor),
+            value => value.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            BufferedValue::Unit => BufferedValue::visit_seq(Vec::new(), visitor),
+            value => value.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            BufferedValue::Seq(values) if values.is_empty() => visitor.visit_unit(),
+            BufferedValue::Map(entries) if entries.is_empty() => visitor.visit_unit(),
+            value => value.deserialize_any(visitor)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf enum identifier ignored_any
+    }
+}
+impl<'de> IntoDeserializer<'de, value::Error> for BufferedValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
@@ -0,0 +1,694 @@
+use bevy::{prelude::*, reflect::{TypeUuid, TypeRegistryArc, TypePath, TypeRegistryInternal}, scene::SceneFilter};
+use serde::{Serialize, Deserialize, de::{DeserializeSeed, Visitor, MapAccess}, ser::SerializeStruct};
+use std::path::Path;
+#[cfg(feature = "ron")]
+use bevy::{asset::AssetLoader, utils::BoxedFuture};
+#[cfg(any(feature = "ron", feature = "binary", feature = "json"))]
+use bevy::asset::{LoadContext, LoadedAsset, AssetPath};
+
+pub mod spawn;
+pub mod instance;
+#[cfg(feature = "hot-reload")]
+pub mod reload;
+pub mod overrides;
+#[cfg(feature = "save-game")]
+pub mod save;
+pub mod extract;
+pub mod boundary;
+pub mod nested;
+pub mod variant;
+pub mod canonical;
+pub mod tree;
+pub mod derived;
+pub mod lenient;
+pub mod migration;
+pub mod format;
+#[cfg(feature = "binary")]
+pub mod binary;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod error;
+#[cfg(feature = "cli")]
+pub mod cli;
+
+use boundary::{PrefabBoundary, PrefabBoundaryKind, ReflectPrefabBoundary};
+use nested::{NestedPrefab, NestedPrefabsSerializer, NestedPrefabsDeserializer, OverridesSerializer, OverridesDeserializer};
+use variant::PrefabVariant;
+use overrides::PrefabOverride;
+use canonical::{CanonicalEntitiesSerializer, CanonicalMapSerializer, VersionedEntitiesSerializer, VersionedMapSerializer};
+use tree::{PrefabLayout, TreeSerializer, TreeNodeDeserializer};
+use format::{PrefabFormat, FormatMigrations};
+use error::{PrefabError, DeserializeErrors};
+use lenient::{PrefabLoadMode, PrefabLoadReport, ComponentsDeserializer, EntitiesDeserializer};
+
+/// Adds the [`Prefab`] asset with its loaders, & the systems that spawn, hot reload & save prefabs.
+///
+/// Only the loaders & systems of enabled cargo features are added.
+/// Change [`lenient::PrefabLoaderSettings`] to change how prefabs are loaded, & add to [`FormatMigrations`] to load files of older format versions.
+/// Both are shared with the loaders, so change them through the resources rather than inserting new ones once the plugin is added.
+#[derive(Default)]
+pub struct PrefabPlugin;
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PrefabMarker>()
+            .register_type::<LeafNode>();
+
+        app.init_resource::<PrefabResources>()
+            .init_resource::<overrides::PrefabSnapshots>()
+            .init_resource::<derived::DerivedComponents>()
+            .init_resource::<lenient::PrefabLoaderSettings>()
+            .init_resource::<FormatMigrations>();
+
+        app.add_asset::<Prefab>();
+        #[cfg(feature = "ron")]
+        app.init_asset_loader::<PrefabLoader>();
+        #[cfg(feature = "binary")]
+        app.init_asset_loader::<binary::BinaryPrefabLoader>();
+        #[cfg(feature = "json")]
+        app.init_asset_loader::<json::JsonPrefabLoader>();
+
+        app.add_event::<spawn::PrefabSpawnFailed>()
+            .add_systems(Update, spawn::spawn_pending_prefabs_system);
+        #[cfg(feature = "hot-reload")]
+        app.add_systems(Update, reload::reload_prefab_instances_system.before(spawn::spawn_pending_prefabs_system));
+
+        #[cfg(feature = "save-game")]
+        app.init_resource::<save::PrefabSaveSettings>()
+            .add_event::<save::SavePrefabRequest>()
+            .add_event::<save::PrefabSaved>()
+            .add_event::<save::PrefabSaveFailed>()
+            .add_systems(PostUpdate, save::save_prefab_requests_system);
+    }
+}
+
+/// Loads `.prefab` files.
+#[cfg(feature = "ron")]
+#[derive(Debug)]
+pub struct PrefabLoader {
+    type_registry: TypeRegistryArc,
+    settings: lenient::PrefabLoaderSettings,
+    migrations: FormatMigrations,
+}
+#[cfg(feature = "ron")]
+impl FromWorld for PrefabLoader {
+    fn from_world(world: &mut World) -> Self {
+        // Share the app's registry, settings & migrations so changes made after the loader is created (e.g. by plugins added after us) are still seen when loading.
+        let type_registry = world.resource::<AppTypeRegistry>();
+
+        PrefabLoader {
+            type_registry: type_registry.0.clone(),
+            settings: world.get_resource::<lenient::PrefabLoaderSettings>().cloned().unwrap_or_default(),
+            migrations: world.get_resource::<FormatMigrations>().cloned().unwrap_or_default()
+        }
+    }
+}
+#[cfg(feature = "ron")]
+impl AssetLoader for PrefabLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(load_prefab(bytes, load_context, &self.type_registry, self.settings.mode(), &self.migrations))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        const EXTENSIONS: &[&str] = &[PrefabFormat::Ron.extension()];
+        EXTENSIONS
+    }
+}
+
+/// Shared by the loaders of every prefab format.
+#[cfg(any(feature = "ron", feature = "binary", feature = "json"))]
+pub(crate) async fn load_prefab(bytes: &[u8], load_context: &mut LoadContext<'_>, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<(), bevy::asset::Error> {
+    let prefab = deserialize_prefab(bytes, load_context.path(), type_registry, mode, migrations)?;
+
+    // Variants are flattened into their base here, so the rest of the app only ever sees complete prefabs.
+    let mut prefab = variant::resolve_variant(prefab, load_context, type_registry, mode, migrations).await?;
+
+    nested::check_nested_entities(&prefab, load_context.path())?;
+
+    // Nested prefabs are loaded alongside this one & expanded when it's spawned.
+    let mut dependencies = Vec::new();
+    for nested in &mut prefab.nested {
+        let path = AssetPath::from(nested.path.as_str()).to_owned();
+
+        if path.path() == load_context.path() {
+            return Err(PrefabError::Cycle {
+                chain: vec![load_context.path().to_path_buf(), path.path().to_path_buf()]
+            }.into());
+        }
+
+        nested.handle = load_context.get_handle(path.clone());
+        dependencies.push(path);
+    }
+
+    load_context.set_default_asset(LoadedAsset::new(prefab).with_dependencies(dependencies));
+    Ok(())
+}
+
+/// Reads a prefab in whichever format `path` has, see [`PrefabFormat`].
+///
+/// Variants aren't flattened onto their base, that's done by the loaders.
+#[cfg_attr(not(any(feature = "ron", feature = "json")), allow(unused_variables))]
+pub fn deserialize_prefab(bytes: &[u8], path: &Path, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let mut prefab: Prefab = match PrefabFormat::from_path(path) {
+        #[cfg(feature = "ron")]
+        PrefabFormat::Ron => deserialize_ron_prefab(bytes, path, type_registry, mode, migrations),
+        #[cfg(feature = "binary")]
+        PrefabFormat::Binary => binary::deserialize_binary_prefab(bytes, path, type_registry, migrations),
+        #[cfg(feature = "json")]
+        PrefabFormat::Json => json::deserialize_json_prefab(bytes, path, type_registry, mode, migrations),
+        #[allow(unreachable_patterns)]
+        format => Err(format.disabled_error(path))
+    }?;
+
+    for skipped in &mut prefab.report.skipped {
+        skipped.path = path.to_path_buf();
+
+        match skipped.entity {
+            Some(entity) => warn!("Skipped `{}` on {entity:?} in {}: {}", skipped.type_path, path.to_string_lossy(), skipped.reason),
+            None => warn!("Skipped resource `{}` in {}: {}", skipped.type_path, path.to_string_lossy(), skipped.reason)
+        }
+    }
+
+    Ok(prefab)
+}
+
+#[cfg(feature = "ron")]
+fn deserialize_ron_prefab(bytes: &[u8], path: &Path, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let errors = DeserializeErrors::default();
+    let prefab_deserializer = PrefabDeserializer {
+        type_registry,
+        mode,
+        migrations,
+        errors: &errors
+    };
+
+    let mut deserializer = ron::de::Deserializer::from_bytes(bytes).map_err(|e| PrefabError::from_ron(e, path))?;
+
+    match prefab_deserializer.deserialize(&mut deserializer) {
+        Ok(prefab) => Ok(prefab),
+        Err(err) => match migrations.outdated_version(&errors, bytes, path) {
+            Some(version) => format::deserialize_migrated(bytes, path, version, type_registry, mode, migrations),
+            None => Err(errors.ron_error(deserializer.span_error(err), path))
+        }
+    }
+}
+
+pub const PREFAB_STRUCT: &str = "Prefab";
+pub const PREFAB_VERSION: &str = "version";
+pub const PREFAB_NAME: &str = "name";
+pub const PREFAB_SCENE: &str = "scene";
+pub const PREFAB_ROOT: &str = "root";
+pub const PREFAB_RESOURCES: &str = "resources";
+pub const PREFAB_NESTED: &str = "nested";
+pub const PREFAB_BASE: &str = "base";
+pub const PREFAB_OVERRIDES: &str = "overrides";
+pub const PREFAB_REMOVED: &str = "removed";
+pub const PREFAB_FIELDS: &[&str] = &[PREFAB_VERSION, PREFAB_NAME, PREFAB_SCENE, PREFAB_ROOT, PREFAB_RESOURCES, PREFAB_NESTED, PREFAB_BASE, PREFAB_OVERRIDES, PREFAB_REMOVED];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PrefabField {
+    Version,
+    Name,
+    Scene,
+    Root,
+    Resources,
+    Nested,
+    Base,
+    Overrides,
+    Removed,
+}
+
+#[derive(TypeUuid, TypePath)]
+#[uuid = "09433411-5448-4168-970e-02341c20e9ed"]
+pub struct Prefab {
+    pub name: String,
+    pub scene: DynamicScene,
+    /// Prefab instances inside this prefab, stored as references to their own files.
+    pub nested: Vec<NestedPrefab>,
+    /// Set while a variant hasn't been flattened into its base yet.
+    pub variant: Option<PrefabVariant>,
+    /// Asset paths of the prefabs this was flattened from, nearest base first.
+    pub bases: Vec<String>,
+    /// What was left out while loading, see [`PrefabLoadMode::Lenient`].
+    pub report: PrefabLoadReport
+}
+impl Prefab {
+    /// The entity in the scene that every other entity descends from.
+    /// 
+    /// The root is the only entity saved without a [`Parent`].
+    pub fn root(&self) -> Option<Entity> {
+        self.scene.entities.iter().find(|entity| {
+            !entity.components.iter().any(|component| component.type_name() == std::any::type_name::<Parent>())
+        }).map(|entity| entity.entity)
+    }
+}
+
+pub struct PrefabSerializer<'a> {
+    pub prefab: &'a Prefab,
+    pub registry: &'a TypeRegistryArc,
+    /// Writes entities in hierarchy order & components sorted by type path, instead of the order they're stored in.
+    /// 
+    /// Keeps saved files from reshuffling on unrelated changes to the world.
+    pub canonical: bool,
+    /// Whether entities are written as a flat `scene` map or a `root` tree. Trees are always written canonically.
+    pub layout: PrefabLayout,
+}
+impl<'a> Serialize for PrefabSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        // Optional sections can only be left out in self-describing formats.
+        let skip_empty = serializer.is_human_readable();
+        
+        let mut state = serializer.serialize_struct(PREFAB_STRUCT, PREFAB_FIELDS.len())?;
+
+        state.serialize_field(PREFAB_VERSION, &format::FORMAT_VERSION)?;
+        state.serialize_field(PREFAB_NAME, &self.prefab.name)?;
+        
+        if self.layout == PrefabLayout::Tree {
+            // Trees can't be read back positionally, as `scene` comes before `root`.
+            if !skip_empty {
+                return Err(serde::ser::Error::custom("the tree layout needs a self-describing format"));
+            }
+
+            state.skip_field(PREFAB_SCENE)?;
+            state.serialize_field(
+                PREFAB_ROOT,
+                &TreeSerializer {
+                    prefab: self.prefab,
+                    registry: self.registry,
+                },
+            )?;
+        } else if self.canonical {
+            state.serialize_field(
+                PREFAB_SCENE,
+                &CanonicalEntitiesSerializer {
+                    prefab: self.prefab,
+                    registry: self.registry,
+                },
+            )?;
+        } else {
+            state.serialize_field(
+                PREFAB_SCENE,
+                &VersionedEntitiesSerializer {
+                    entities: &self.prefab.scene.entities,
+                    registry: self.registry,
+                },
+            )?;
+            if skip_empty {
+                state.skip_field(PREFAB_ROOT)?;
+            }
+        }
+
+        // Resources are optional, most prefabs don't carry any.
+        if skip_empty && self.prefab.scene.resources.is_empty() {
+            state.skip_field(PREFAB_RESOURCES)?;
+        } else if self.canonical || self.layout == PrefabLayout::Tree {
+            state.serialize_field(
+                PREFAB_RESOURCES,
+                &CanonicalMapSerializer {
+                    entries: &self.prefab.scene.resources,
+                    registry: self.registry,
+                },
+            )?;
+        } else {
+            state.serialize_field(
+                PREFAB_RESOURCES,
+                &VersionedMapSerializer {
+                    entries: &self.prefab.scene.resources,
+                    registry: self.registry,
+                },
+            )?;
+        }
+
+        if skip_empty && self.prefab.nested.is_empty() {
+            state.skip_field(PREFAB_NESTED)?;
+        } else {
+            state.serialize_field(
+                PREFAB_NESTED,
+                &NestedPrefabsSerializer {
+                    nested: &self.prefab.nested,
+                    registry: self.registry,
+                },
+            )?;
+        }
+
+        // Variant fields are only written for prefabs that haven't been flattened into their base.
+        match (&self.prefab.variant, skip_empty) {
+            (Some(variant), true) => {
+                state.serialize_field(PREFAB_BASE, &variant.base)?;
+                state.serialize_field(
+                    PREFAB_OVERRIDES,
+                    &OverridesSerializer {
+                        overrides: &variant.overrides,
+                        registry: self.registry,
+                    },
+                )?;
+                state.serialize_field(PREFAB_REMOVED, &variant.removed)?;
+            }
+            (None, true) => {
+                state.skip_field(PREFAB_BASE)?;
+                state.skip_field(PREFAB_OVERRIDES)?;
+                state.skip_field(PREFAB_REMOVED)?;
+            }
+            (variant, false) => {
+                state.serialize_field(PREFAB_BASE, &variant.as_ref().map(|variant| &variant.base))?;
+                state.serialize_field(
+                    PREFAB_OVERRIDES,
+                    &OverridesSerializer {
+                        overrides: variant.as_ref().map(|variant| variant.overrides.as_slice()).unwrap_or_default(),
+                        registry: self.registry,
+                    },
+                )?;
+                state.serialize_field(PREFAB_REMOVED, variant.as_ref().map(|variant| variant.removed.as_slice()).unwrap_or_default())?;
+            }
+        }
+
+        state.end()
+    }
+}
+/// Serializes `prefab` into the pretty-printed, canonically ordered RON stored in `.prefab` files.
+#[cfg(feature = "ron")]
+pub fn serialize_prefab(prefab: &Prefab, registry: &TypeRegistryArc) -> Result<String, ron::Error> {
+    serialize_prefab_as(prefab, registry, PrefabLayout::Flat)
+}
+
+/// Same as [`serialize_prefab`], with entities in the given layout.
+#[cfg(feature = "ron")]
+pub fn serialize_prefab_as(prefab: &Prefab, registry: &TypeRegistryArc, layout: PrefabLayout) -> Result<String, ron::Error> {
+    let prefab_serializer = PrefabSerializer {
+        prefab,
+        registry,
+        canonical: true,
+        layout
+    };
+
+    let pretty_config = ron::ser::PrettyConfig::default()
+        .indentor("  ".to_string())
+        .new_line("\n".to_string());
+    
+    ron::ser::to_string_pretty(&prefab_serializer, pretty_config)
+}
+
+pub struct PrefabDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryArc,
+    pub mode: PrefabLoadMode,
+    /// Documents of a version these need to migrate from fail with [`DeserializeErrors`] noting the version, to be migrated & loaded again.
+    pub migrations: &'a FormatMigrations,
+    pub errors: &'a DeserializeErrors,
+}
+impl<'a, 'de> DeserializeSeed<'de> for PrefabDeserializer<'a> {
+    type Value = Prefab;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let type_registry = self.type_registry.internal.read();
+
+        let prefab = deserializer.deserialize_struct(
+            PREFAB_STRUCT,
+            PREFAB_FIELDS,
+            PrefabVisitor {
+                type_registry: &type_registry,
+                mode: self.mode,
+                migrations: self.migrations,
+                errors: self.errors,
+            },
+        )?;
+
+        Ok(prefab)
+    }
+}
+
+/// Only variants, prefabs with a base, can override or remove anything.
+fn variant_from_fields<E: serde::de::Error>(base: Option<String>, overrides: Vec<PrefabOverride>, removed: Vec<Entity>) -> Result<Option<PrefabVariant>, E> {
+    match base {
+        Some(base) => Ok(Some(PrefabVariant {
+            base,
+            overrides,
+            removed
+        })),
+        None if overrides.is_empty() && removed.is_empty() => Ok(None),
+        None => Err(E::custom(format_args!("`{PREFAB_OVERRIDES}` & `{PREFAB_REMOVED}` require a `{PREFAB_BASE}`")))
+    }
+}
+
+struct PrefabVisitor<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+    pub mode: PrefabLoadMode,
+    pub migrations: &'a FormatMigrations,
+    pub errors: &'a DeserializeErrors,
+}
+
+impl<'a, 'de> Visitor<'de> for PrefabVisitor<'a> {
+    type Value = Prefab;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("Prefab Struct")
+    }
+    
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>, {
+        
+        let version = seq.next_element()?.ok_or_else(|| self.errors.missing_field(PREFAB_VERSION))?;
+        format::check_version(version).map_err(serde::de::Error::custom)?;
+        if self.migrations.needs_migration(version) {
+            return Err(self.errors.outdated(version));
+        }
+
+        let name = seq.next_element()?.ok_or_else(|| self.errors.missing_field(PREFAB_NAME))?;
+
+        let (entities, mut skipped) = seq.next_element_seed(EntitiesDeserializer {
+            type_registry: self.type_registry,
+            mode: self.mode,
+            errors: self.errors
+        })?.ok_or_else(|| self.errors.missing_field(PREFAB_SCENE))?;
+
+        let (resources, skipped_resources) = seq.next_element_seed(ComponentsDeserializer {
+            type_registry: self.type_registry,
+            mode: self.mode,
+            resources: true,
+            entity: None,
+            errors: self.errors
+        })?.unwrap_or_default();
+        skipped.extend(skipped_resources);
+
+        let nested = seq.next_element_seed(NestedPrefabsDeserializer {
+            type_registry: self.type_registry
+        })?.unwrap_or_default();
+
+        let base = seq.next_element::<Option<String>>()?.flatten();
+        let overrides = seq.next_element_seed(OverridesDeserializer {
+            type_registry: self.type_registry
+        })?.unwrap_or_default();
+        let removed = seq.next_element()?.unwrap_or_default();
+        
+        let scene = DynamicScene { 
+            resources,
+            entities
+        };
+
+        Ok(Prefab { 
+            name,
+            scene,
+            nested,
+            variant: variant_from_fields::<A::Error>(base, overrides, removed)?,
+            bases: Vec::default(),
+            report: PrefabLoadReport {
+                skipped
+            }
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut version = None;
+        let mut name = None;
+        let mut entities = None;
+        let mut root = None;
+        let mut resources = None;
+        let mut nested = None;
+        let mut base = None;
+        let mut overrides = None;
+        let mut removed = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                PrefabField::Version => {
+                    if version.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_VERSION));
+                    }
+
+                    let file_version = map.next_value()?;
+                    format::check_version(file_version).map_err(serde::de::Error::custom)?;
+                    if self.migrations.needs_migration(file_version) {
+                        return Err(self.errors.outdated(file_version));
+                    }
+
+                    version = Some(file_version);
+                }
+                PrefabField::Name => {
+                    if name.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_NAME));
+                    }
+
+                    name = Some(map.next_value()?);
+                }
+                PrefabField::Scene => {
+                    if entities.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_SCENE));
+                    }
+
+                    entities = Some(map.next_value_seed(EntitiesDeserializer {
+                        type_registry: self.type_registry,
+                        mode: self.mode,
+                        errors: self.errors
+                    })?);
+                }
+                PrefabField::Root => {
+                    if root.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_ROOT));
+                    }
+
+                    root = Some(map.next_value_seed(TreeNodeDeserializer {
+                        type_registry: self.type_registry,
+                        mode: self.mode,
+                        errors: self.errors
+                    })?);
+                }
+                PrefabField::Resources => {
+                    if resources.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_RESOURCES));
+                    }
+
+                    resources = Some(map.next_value_seed(ComponentsDeserializer {
+                        type_registry: self.type_registry,
+                        mode: self.mode,
+                        resources: true,
+                        entity: None,
+                        errors: self.errors
+                    })?);
+                }
+                PrefabField::Nested => {
+                    if nested.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_NESTED));
+                    }
+
+                    nested = Some(map.next_value_seed(NestedPrefabsDeserializer {
+                        type_registry: self.type_registry
+                    })?);
+                }
+                PrefabField::Base => {
+                    if base.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_BASE));
+                    }
+
+                    base = Some(map.next_value()?);
+                }
+                PrefabField::Overrides => {
+                    if overrides.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_OVERRIDES));
+                    }
+
+                    overrides = Some(map.next_value_seed(OverridesDeserializer {
+                        type_registry: self.type_registry
+                    })?);
+                }
+                PrefabField::Removed => {
+                    if removed.is_some() {
+                        return Err(serde::de::Error::duplicate_field(PREFAB_REMOVED));
+                    }
+
+                    removed = Some(map.next_value()?);
+                }
+            }
+        }
+
+        // Files without a version predate it, they're version 0.
+        if version.is_none() && self.migrations.needs_migration(0) {
+            return Err(self.errors.outdated(0));
+        }
+
+        let name = name.ok_or_else(|| self.errors.missing_field(PREFAB_NAME))?;
+        let root_layout = root.is_some();
+        let (entities, mut skipped) = match (entities, root) {
+            (Some(entities), None) => entities,
+            (None, Some(root)) => tree::tree_entities(root),
+            (Some(_), Some(_)) => return Err(serde::de::Error::custom(format_args!("`{PREFAB_SCENE}` & `{PREFAB_ROOT}` can't both be used"))),
+            (None, None) => return Err(self.errors.missing_field(PREFAB_SCENE))
+        };
+        let (resources, skipped_resources) = resources.unwrap_or_default();
+        skipped.extend(skipped_resources);
+        let nested = nested.unwrap_or_default();
+        let overrides = overrides.unwrap_or_default();
+        let removed = removed.unwrap_or_default();
+
+        // Tree ids are implied by position, which says nothing about the entities of a base.
+        if root_layout && base.is_some() {
+            return Err(serde::de::Error::custom(format_args!("variants can't use `{PREFAB_ROOT}`, use `{PREFAB_SCENE}` instead")));
+        }
+
+        let scene = DynamicScene { 
+            resources,
+            entities
+        };
+
+        Ok(Prefab { 
+            name,
+            scene,
+            nested,
+            variant: variant_from_fields::<A::Error>(base, overrides, removed)?,
+            bases: Vec::default(),
+            report: PrefabLoadReport {
+                skipped
+            }
+        })
+    }
+}
+
+/// Which resource types get saved into prefabs alongside their entities.
+/// 
+/// Prefabs carry no resources unless they're explicitly allowed here.
+#[derive(Resource, Clone)]
+pub struct PrefabResources(pub SceneFilter);
+impl Default for PrefabResources {
+    fn default() -> Self {
+        Self(SceneFilter::deny_all())
+    }
+}
+impl PrefabResources {
+    pub fn allow<T: Resource>(&mut self) -> &mut Self {
+        self.0.allow::<T>();
+        self
+    }
+
+    pub fn deny<T: Resource>(&mut self) -> &mut Self {
+        self.0.deny::<T>();
+        self
+    }
+}
+
+/// Placed on prefab instance roots, so they're saved as references when nested in other prefabs.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, PrefabBoundary)]
+pub struct PrefabMarker;
+impl PrefabBoundary for PrefabMarker {
+    const BOUNDARY: PrefabBoundaryKind = PrefabBoundaryKind::NestedPrefab;
+}
+
+/// Saved into prefabs without its children.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, PrefabBoundary)]
+pub struct LeafNode;
+impl PrefabBoundary for LeafNode {
+    const BOUNDARY: PrefabBoundaryKind = PrefabBoundaryKind::DropChildren;
+}
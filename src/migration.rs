@@ -0,0 +1,98 @@
+use std::borrow::Cow;
+
+use bevy::reflect::{FromType, Reflect, TypeRegistration, TypeRegistryInternal};
+
+/// Separates a type path from the version it was saved with, `my_game::Health@2`.
+pub const VERSION_SEPARATOR: char = '@';
+
+/// Older type paths of a component or resource, so files saved before it was renamed or moved keep loading.
+///
+/// Register it with `#[reflect(PrefabAliases)]` so the loader picks it up from the type registry.
+pub trait PrefabAliases {
+    const ALIASES: &'static [&'static str];
+}
+
+/// Type data for types with [`PrefabAliases`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectPrefabAliases {
+    pub aliases: &'static [&'static str],
+}
+impl<T: PrefabAliases> FromType<T> for ReflectPrefabAliases {
+    fn from_type() -> Self {
+        Self {
+            aliases: T::ALIASES
+        }
+    }
+}
+
+/// Turns a value saved with one version of a type into the next version.
+pub type PrefabMigration = fn(ron::Value) -> Result<ron::Value, String>;
+
+/// Migrations for a component or resource whose fields changed, in order.
+///
+/// The first migration takes unversioned values to version 1, the current version is the number of migrations.
+/// Values are migrated as [`ron::Value`]s, which don't keep enum variant names, so migrations should rewrite fields holding enums themselves.
+///
+/// Register it with `#[reflect(PrefabMigrations)]`.
+pub trait PrefabMigrations {
+    const MIGRATIONS: &'static [PrefabMigration];
+}
+
+/// Type data for types with [`PrefabMigrations`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectPrefabMigrations {
+    pub migrations: &'static [PrefabMigration],
+}
+impl<T: PrefabMigrations> FromType<T> for ReflectPrefabMigrations {
+    fn from_type() -> Self {
+        Self {
+            migrations: T::MIGRATIONS
+        }
+    }
+}
+impl ReflectPrefabMigrations {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Takes `value` from `version` to the current version.
+    pub fn migrate(&self, mut value: ron::Value, version: u32) -> Result<ron::Value, String> {
+        for (from, migration) in self.migrations.iter().enumerate().skip(version as usize) {
+            value = migration(value).map_err(|err| format!("migrating from version {from}: {err}"))?;
+        }
+
+        Ok(value)
+    }
+}
+
+/// Splits a saved key into its type path & version, unversioned keys are version 0.
+pub fn split_version(key: &str) -> Result<(&str, u32), String> {
+    match key.rsplit_once(VERSION_SEPARATOR) {
+        Some((type_path, version)) => version.parse()
+            .map(|version| (type_path, version))
+            .map_err(|_| format!("`{version}` in `{key}` isn't a version")),
+        None => Ok((key, 0))
+    }
+}
+
+/// The registration for `type_path`, falling back to types that list it in their [`PrefabAliases`].
+pub fn registration_for<'a>(type_registry: &'a TypeRegistryInternal, type_path: &str) -> Option<&'a TypeRegistration> {
+    type_registry.get_with_name(type_path).or_else(|| {
+        type_registry.iter().find(|registration| {
+            registration.data::<ReflectPrefabAliases>().is_some_and(|aliases| aliases.aliases.contains(&type_path))
+        })
+    })
+}
+
+/// The key `value` is saved under, its type path followed by its version if it has migrations.
+pub fn versioned_type_path<'a>(value: &'a dyn Reflect, type_registry: &TypeRegistryInternal) -> Cow<'a, str> {
+    let version = type_registry.get_with_name(value.type_name())
+        .and_then(|registration| registration.data::<ReflectPrefabMigrations>())
+        .map(|migrations| migrations.version())
+        .unwrap_or_default();
+
+    match version {
+        0 => Cow::Borrowed(value.type_name()),
+        version => Cow::Owned(format!("{}{VERSION_SEPARATOR}{version}", value.type_name()))
+    }
+}
@@ -0,0 +1,450 @@
+use std::path::Path;
+
+use bevy::{prelude::*, reflect::{TypeRegistryArc, TypeRegistryInternal, serde::{ReflectSerializer, UntypedReflectDeserializer}}};
+use serde::{Serialize, Deserialize, de::{DeserializeSeed, Visitor, MapAccess, SeqAccess}, ser::{SerializeStruct, SerializeMap, SerializeSeq}};
+
+use crate::{Prefab, error::PrefabError, migration::registration_for, overrides::PrefabOverride};
+
+pub const NESTED_STRUCT: &str = "NestedPrefab";
+pub const NESTED_PATH: &str = "path";
+pub const NESTED_OVERRIDES: &str = "overrides";
+pub const NESTED_FIELDS: &[&str] = &[NESTED_PATH, NESTED_OVERRIDES];
+
+pub const OVERRIDE_STRUCT: &str = "Override";
+pub const OVERRIDE_SOURCE: &str = "source";
+pub const OVERRIDE_COMPONENT: &str = "component";
+pub const OVERRIDE_PATH: &str = "path";
+pub const OVERRIDE_VALUE: &str = "value";
+pub const OVERRIDE_FIELDS: &[&str] = &[OVERRIDE_SOURCE, OVERRIDE_COMPONENT, OVERRIDE_PATH, OVERRIDE_VALUE];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum NestedField {
+    Path,
+    Overrides,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum OverrideField {
+    Source,
+    Component,
+    Path,
+    Value,
+}
+
+/// A prefab instance inside another prefab, saved as a reference to its own `.prefab` file.
+#[derive(Debug)]
+pub struct NestedPrefab {
+    /// The entity in the outer prefab standing in for the nested instance's root.
+    pub entity: Entity,
+    /// Asset path of the nested prefab.
+    pub path: String,
+    /// Set by the loader, which loads the nested prefab as a dependency.
+    pub handle: Handle<Prefab>,
+    /// How the nested instance differs from its prefab.
+    pub overrides: Vec<PrefabOverride>,
+}
+
+/// Checks that every prefab nested in `prefab`, loaded from `path`, stands in for one of its entities.
+///
+/// Variants have to be flattened first, their nested prefabs may stand in for entities of their base.
+pub fn check_nested_entities(prefab: &Prefab, path: &Path) -> Result<(), PrefabError> {
+    match prefab.nested.iter().find(|nested| !prefab.scene.entities.iter().any(|entity| entity.entity == nested.entity)) {
+        Some(nested) => Err(PrefabError::InvalidEntity {
+            path: Some(path.to_path_buf()),
+            entity: Some(nested.entity),
+            reason: format!("nests {} but isn't in the prefab", nested.path)
+        }),
+        None => Ok(())
+    }
+}
+
+pub struct NestedPrefabsSerializer<'a> {
+    pub nested: &'a [NestedPrefab],
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for NestedPrefabsSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_map(Some(self.nested.len()))?;
+
+        for nested in self.nested {
+            state.serialize_entry(
+                &nested.entity,
+                &NestedPrefabSerializer {
+                    nested,
+                    registry: self.registry
+                }
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+struct NestedPrefabSerializer<'a> {
+    nested: &'a NestedPrefab,
+    registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for NestedPrefabSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_struct(NESTED_STRUCT, NESTED_FIELDS.len())?;
+
+        state.serialize_field(NESTED_PATH, &self.nested.path)?;
+        state.serialize_field(
+            NESTED_OVERRIDES,
+            &OverridesSerializer {
+                overrides: &self.nested.overrides,
+                registry: self.registry
+            }
+        )?;
+
+        state.end()
+    }
+}
+
+pub struct OverridesSerializer<'a> {
+    pub overrides: &'a [PrefabOverride],
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for OverridesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let registry = self.registry.read();
+        let mut state = serializer.serialize_seq(Some(self.overrides.len()))?;
+
+        for prefab_override in self.overrides {
+            state.serialize_element(&OverrideSerializer {
+                prefab_override,
+                registry: &registry
+            })?;
+        }
+
+        state.end()
+    }
+}
+
+struct OverrideSerializer<'a> {
+    prefab_override: &'a PrefabOverride,
+    registry: &'a TypeRegistryInternal,
+}
+impl<'a> Serialize for OverrideSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_struct(OVERRIDE_STRUCT, OVERRIDE_FIELDS.len())?;
+
+        state.serialize_field(OVERRIDE_SOURCE, &self.prefab_override.source)?;
+        state.serialize_field(OVERRIDE_COMPONENT, &self.prefab_override.component)?;
+        state.serialize_field(OVERRIDE_PATH, &self.prefab_override.path)?;
+        state.serialize_field(
+            OVERRIDE_VALUE,
+            &self.prefab_override.value.as_ref().map(|value| ReflectSerializer::new(&**value, self.registry))
+        )?;
+
+        state.end()
+    }
+}
+
+pub struct NestedPrefabsDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> DeserializeSeed<'de> for NestedPrefabsDeserializer<'a> {
+    type Value = Vec<NestedPrefab>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(NestedPrefabsVisitor {
+            type_registry: self.type_registry
+        })
+    }
+}
+
+struct NestedPrefabsVisitor<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> Visitor<'de> for NestedPrefabsVisitor<'a> {
+    type Value = Vec<NestedPrefab>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("map of nested prefabs")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut nested = Vec::new();
+
+        while let Some(entity) = map.next_key::<Entity>()? {
+            let (path, overrides) = map.next_value_seed(NestedPrefabDeserializer {
+                type_registry: self.type_registry
+            })?;
+
+            nested.push(NestedPrefab {
+                entity,
+                path,
+                handle: Handle::default(),
+                overrides
+            });
+        }
+
+        Ok(nested)
+    }
+}
+
+struct NestedPrefabDeserializer<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> DeserializeSeed<'de> for NestedPrefabDeserializer<'a> {
+    type Value = (String, Vec<PrefabOverride>);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            NESTED_STRUCT,
+            NESTED_FIELDS,
+            NestedPrefabVisitor {
+                type_registry: self.type_registry
+            }
+        )
+    }
+}
+
+struct NestedPrefabVisitor<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> Visitor<'de> for NestedPrefabVisitor<'a> {
+    type Value = (String, Vec<PrefabOverride>);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("NestedPrefab Struct")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let path = seq.next_element()?.ok_or_else(|| serde::de::Error::missing_field(NESTED_PATH))?;
+        let overrides = seq.next_element_seed(OverridesDeserializer {
+            type_registry: self.type_registry
+        })?.unwrap_or_default();
+
+        Ok((path, overrides))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut path = None;
+        let mut overrides = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                NestedField::Path => {
+                    if path.is_some() {
+                        return Err(serde::de::Error::duplicate_field(NESTED_PATH));
+                    }
+
+                    path = Some(map.next_value()?);
+                }
+                NestedField::Overrides => {
+                    if overrides.is_some() {
+                        return Err(serde::de::Error::duplicate_field(NESTED_OVERRIDES));
+                    }
+
+                    overrides = Some(map.next_value_seed(OverridesDeserializer {
+                        type_registry: self.type_registry
+                    })?);
+                }
+            }
+        }
+
+        let path = path.ok_or_else(|| serde::de::Error::missing_field(NESTED_PATH))?;
+
+        Ok((path, overrides.unwrap_or_default()))
+    }
+}
+
+pub struct OverridesDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> DeserializeSeed<'de> for OverridesDeserializer<'a> {
+    type Value = Vec<PrefabOverride>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for OverridesDeserializer<'a> {
+    type Value = Vec<PrefabOverride>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of overrides")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let mut overrides = Vec::new();
+
+        while let Some(prefab_override) = seq.next_element_seed(OverrideDeserializer {
+            type_registry: self.type_registry
+        })? {
+            overrides.push(prefab_override);
+        }
+
+        Ok(overrides)
+    }
+}
+
+struct OverrideDeserializer<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> DeserializeSeed<'de> for OverrideDeserializer<'a> {
+    type Value = PrefabOverride;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(OVERRIDE_STRUCT, OVERRIDE_FIELDS, self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for OverrideDeserializer<'a> {
+    type Value = PrefabOverride;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("Override Struct")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let source = seq.next_element()?.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_SOURCE))?;
+        let component = seq.next_element::<String>()?.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_COMPONENT))?;
+        let path = seq.next_element()?.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_PATH))?;
+        let value = seq.next_element_seed(OptionalReflectDeserializer {
+            type_registry: self.type_registry
+        })?.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_VALUE))?;
+
+        Ok(PrefabOverride {
+            source,
+            component: registered_type_path(self.type_registry, component),
+            path,
+            value
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut source = None;
+        let mut component = None;
+        let mut path = None;
+        let mut value = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                OverrideField::Source => {
+                    if source.is_some() {
+                        return Err(serde::de::Error::duplicate_field(OVERRIDE_SOURCE));
+                    }
+
+                    source = Some(map.next_value()?);
+                }
+                OverrideField::Component => {
+                    if component.is_some() {
+                        return Err(serde::de::Error::duplicate_field(OVERRIDE_COMPONENT));
+                    }
+
+                    component = Some(map.next_value::<String>()?);
+                }
+                OverrideField::Path => {
+                    if path.is_some() {
+                        return Err(serde::de::Error::duplicate_field(OVERRIDE_PATH));
+                    }
+
+                    path = Some(map.next_value()?);
+                }
+                OverrideField::Value => {
+                    if value.is_some() {
+                        return Err(serde::de::Error::duplicate_field(OVERRIDE_VALUE));
+                    }
+
+                    value = Some(map.next_value_seed(OptionalReflectDeserializer {
+                        type_registry: self.type_registry
+                    })?);
+                }
+            }
+        }
+
+        Ok(PrefabOverride {
+            source: source.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_SOURCE))?,
+            component: registered_type_path(self.type_registry, component.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_COMPONENT))?),
+            path: path.unwrap_or_default(),
+            value: value.ok_or_else(|| serde::de::Error::missing_field(OVERRIDE_VALUE))?
+        })
+    }
+}
+
+/// Overrides are applied by type path, so ones saved under an alias are stored under the registered type path instead.
+fn registered_type_path(type_registry: &TypeRegistryInternal, component: String) -> String {
+    registration_for(type_registry, &component)
+        .map(|registration| registration.type_name().to_owned())
+        .unwrap_or(component)
+}
+
+/// Reads `Option<ReflectSerializer>` back.
+struct OptionalReflectDeserializer<'a> {
+    type_registry: &'a TypeRegistryInternal,
+}
+impl<'a, 'de> DeserializeSeed<'de> for OptionalReflectDeserializer<'a> {
+    type Value = Option<Box<dyn Reflect>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_option(self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for OptionalReflectDeserializer<'a> {
+    type Value = Option<Box<dyn Reflect>>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("optional reflect value")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error, {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>, {
+        UntypedReflectDeserializer::new(self.type_registry).deserialize(deserializer).map(Some)
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use bevy::{prelude::*, asset::HandleId, scene::DynamicEntity, reflect::{ReflectRef, GetPath, TypeRegistryInternal}, ecs::reflect::ReflectMapEntities, utils::HashMap};
+
+use crate::{Prefab, instance::PrefabInstance};
+
+/// A component, or a field of one, on a prefab instance whose value differs from the prefab it was spawned from.
+#[derive(Debug)]
+pub struct PrefabOverride {
+    /// The entity in [`Prefab::scene`] the override applies to.
+    pub source: Entity,
+    /// Type name of the overridden component.
+    pub component: String,
+    /// Reflect path to the overridden field, empty if the override covers the whole component.
+    pub path: String,
+    /// The instance's value, `None` if the component was removed from the instance.
+    pub value: Option<Box<dyn Reflect>>,
+}
+
+/// The prefab contents each instance was last written from, per prefab asset.
+///
+/// Overrides are diffed against these rather than the [`Prefab`] asset itself, which is already replaced by the time a hot reload happens.
+#[derive(Resource, Default)]
+pub struct PrefabSnapshots(HashMap<HandleId, Arc<DynamicScene>>);
+impl PrefabSnapshots {
+    pub fn get(&self, handle: &Handle<Prefab>) -> Option<Arc<DynamicScene>> {
+        self.0.get(&handle.id()).cloned()
+    }
+
+    pub(crate) fn insert(&mut self, handle: &Handle<Prefab>, scene: &DynamicScene) {
+        self.0.insert(handle.id(), Arc::new(clone_scene(scene)));
+    }
+
+    #[cfg(feature = "hot-reload")]
+    pub(crate) fn remove(&mut self, handle: &Handle<Prefab>) {
+        self.0.remove(&handle.id());
+    }
+}
+
+pub(crate) fn clone_scene(scene: &DynamicScene) -> DynamicScene {
+    DynamicScene {
+        resources: scene.resources.iter().map(|resource| resource.clone_value()).collect(),
+        entities: scene.entities.iter().map(|entity| DynamicEntity {
+            entity: entity.entity,
+            components: entity.components.iter().map(|component| component.clone_value()).collect()
+        }).collect()
+    }
+}
+
+/// Lists every field of `instance` that differs from the prefab it was spawned from.
+///
+/// Components referencing entities (like [`Children`]) aren't tracked, their values are only meaningful within one world.
+pub fn prefab_overrides(world: &World, instance: Entity) -> Vec<PrefabOverride> {
+    let Some(prefab_instance) = world.get::<PrefabInstance>(instance) else {
+        return Vec::default();
+    };
+    let Some(snapshot) = world.resource::<PrefabSnapshots>().get(&prefab_instance.handle) else {
+        return Vec::default();
+    };
+
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+
+    diff_instance(world, prefab_instance, &snapshot, &type_registry)
+}
+
+pub(crate) fn diff_instance(world: &World, prefab_instance: &PrefabInstance, scene: &DynamicScene, type_registry: &TypeRegistryInternal) -> Vec<PrefabOverride> {
+    let mut overrides = Vec::new();
+
+    for entity in &scene.entities {
+        let Some(spawned) = prefab_instance.entity_map.get(entity.entity).and_then(|spawned| world.get_entity(spawned)) else {
+            continue;
+        };
+
+        for component in &entity.components {
+            let Some(registration) = type_registry.get_with_name(component.type_name()) else {
+                continue;
+            };
+            if registration.data::<ReflectMapEntities>().is_some() {
+                continue;
+            }
+            let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+                continue;
+            };
+
+            let Some(current) = reflect_component.reflect(spawned) else {
+                overrides.push(PrefabOverride {
+                    source: entity.entity,
+                    component: component.type_name().to_owned(),
+                    path: String::new(),
+                    value: None
+                });
+                continue;
+            };
+
+            let mut changed = Vec::new();
+            diff_reflect(&**component, current, String::new(), &mut changed);
+
+            overrides.extend(changed.into_iter().map(|(path, value)| PrefabOverride {
+                source: entity.entity,
+                component: component.type_name().to_owned(),
+                path,
+                value: Some(value)
+            }));
+        }
+    }
+
+    overrides
+}
+
+/// Collects the paths of every field in `current` that differs from `source`.
+///
+/// Recurses into structs, tuples & lists of matching shape, anything else is compared as a whole.
+fn diff_reflect(source: &dyn Reflect, current: &dyn Reflect, path: String, changed: &mut Vec<(String, Box<dyn Reflect>)>) {
+    match (source.reflect_ref(), current.reflect_ref()) {
+        (ReflectRef::Struct(source), ReflectRef::Struct(current)) => {
+            for (i, field) in current.iter_fields().enumerate() {
+                let Some(name) = current.name_at(i) else {
+                    continue;
+                };
+
+                match source.field(name) {
+                    Some(source_field) => diff_reflect(source_field, field, format!("{path}.{name}"), changed),
+                    None => changed.push((format!("{path}.{name}"), field.clone_value()))
+                }
+            }
+        }
+        (ReflectRef::TupleStruct(source), ReflectRef::TupleStruct(current)) if source.field_len() == current.field_len() => {
+            for (i, (source_field, field)) in source.iter_fields().zip(current.iter_fields()).enumerate() {
+                diff_reflect(source_field, field, format!("{path}.{i}"), changed);
+            }
+        }
+        (ReflectRef::Tuple(source), ReflectRef::Tuple(current)) if source.field_len() == current.field_len() => {
+            for (i, (source_field, field)) in source.iter_fields().zip(current.iter_fields()).enumerate() {
+                diff_reflect(source_field, field, format!("{path}.{i}"), changed);
+            }
+        }
+        (ReflectRef::List(source), ReflectRef::List(current)) if source.len() == current.len() => {
+            for (i, (source_item, item)) in source.iter().zip(current.iter()).enumerate() {
+                diff_reflect(source_item, item, format!("{path}[{i}]"), changed);
+            }
+        }
+        (ReflectRef::Array(source), ReflectRef::Array(current)) if source.len() == current.len() => {
+            for (i, (source_item, item)) in source.iter().zip(current.iter()).enumerate() {
+                diff_reflect(source_item, item, format!("{path}[{i}]"), changed);
+            }
+        }
+        _ => {
+            if current.reflect_partial_eq(source) != Some(true) {
+                changed.push((path, current.clone_value()));
+            }
+        }
+    }
+}
+
+/// Writes `overrides` back onto `instance`, e.g. after the prefab was reloaded.
+pub fn apply_prefab_overrides(world: &mut World, instance: Entity, overrides: &[PrefabOverride]) {
+    let Some(prefab_instance) = world.get::<PrefabInstance>(instance) else {
+        return;
+    };
+    let spawned_entities = overrides.iter().map(|prefab_override| prefab_instance.entity_map.get(prefab_override.source)).collect::<Vec<_>>();
+
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    for (prefab_override, spawned) in overrides.iter().zip(spawned_entities) {
+        let Some(spawned) = spawned else {
+            continue;
+        };
+        let Some(reflect_component) = type_registry.get_with_name(&prefab_override.component).and_then(|registration| registration.data::<ReflectComponent>()) else {
+            continue;
+        };
+        let Some(mut entity_mut) = world.get_entity_mut(spawned) else {
+            continue;
+        };
+
+        let Some(value) = &prefab_override.value else {
+            reflect_component.remove(&mut entity_mut);
+            continue;
+        };
+
+        if prefab_override.path.is_empty() {
+            reflect_component.apply_or_insert(&mut entity_mut, &**value);
+            continue;
+        }
+
+        let Some(mut component) = reflect_component.reflect_mut(&mut entity_mut) else {
+            continue;
+        };
+
+        match component.reflect_path_mut(&prefab_override.path) {
+            Ok(field) => field.apply(&**value),
+            Err(err) => warn!("Couldn't reapply override '{}{}': {err}", prefab_override.component, prefab_override.path)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::{prelude::*, ecs::event::ManualEventReader, hierarchy::despawn_with_children_recursive, utils::HashSet};
+
+use crate::{Prefab, derived::insert_derived_components, instance::{PrefabInstance, PrefabSource, component_types}, overrides::{PrefabSnapshots, diff_instance, apply_prefab_overrides}};
+
+/// Re-applies modified [`Prefab`] assets to every live [`PrefabInstance`] of them.
+pub fn reload_prefab_instances_system(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<AssetEvent<Prefab>>>
+) {
+    let mut modified = HashSet::new();
+    let mut removed = Vec::new();
+
+    for event in reader.iter(world.resource::<Events<AssetEvent<Prefab>>>()) {
+        match event {
+            AssetEvent::Modified { handle } => {
+                modified.insert(handle.clone_weak());
+            }
+            AssetEvent::Removed { handle } => {
+                removed.push(handle.clone_weak());
+            }
+            AssetEvent::Created { .. } => {}
+        }
+    }
+
+    for handle in removed {
+        world.resource_mut::<PrefabSnapshots>().remove(&handle);
+    }
+
+    if modified.is_empty() {
+        return;
+    }
+
+    let instances = {
+        let mut query = world.query::<(Entity, &PrefabInstance)>();
+
+        query.iter(world).filter(|(_, prefab_instance)| modified.contains(&prefab_instance.handle)).map(|(entity, _)| entity).collect::<Vec<_>>()
+    };
+
+    world.resource_scope(|world, prefabs: Mut<Assets<Prefab>>| {
+        for instance in instances {
+            let Some(mut prefab_instance) = world.entity_mut(instance).take::<PrefabInstance>() else {
+                continue;
+            };
+
+            let Some(prefab) = prefabs.get(&prefab_instance.handle) else {
+                world.entity_mut(instance).insert(prefab_instance);
+                continue;
+            };
+
+            // Diff against what the instance was spawned from, the asset itself already holds the new contents.
+            let overrides = world.resource::<PrefabSnapshots>().get(&prefab_instance.handle).map(|snapshot| {
+                let type_registry = world.resource::<AppTypeRegistry>().read();
+
+                diff_instance(world, &prefab_instance, &snapshot, &type_registry)
+            }).unwrap_or_default();
+
+            reload_prefab_instance(world, instance, &mut prefab_instance, prefab);
+
+            world.entity_mut(instance).insert(prefab_instance);
+
+            apply_prefab_overrides(world, instance, &overrides);
+        }
+
+        for handle in &modified {
+            if let Some(prefab) = prefabs.get(handle) {
+                world.resource_mut::<PrefabSnapshots>().insert(handle, &prefab.scene);
+            }
+        }
+    });
+}
+
+/// Brings an existing instance up to date with `prefab`.
+///
+/// Entities & components that were removed from the prefab are removed from the instance, new ones are added and existing ones take on the prefab's values.
+/// Children that weren't spawned from the prefab (like those under a [`crate::LeafNode`]) are left in place.
+/// Changes made to the instance, including a moved root [`Transform`], are diffed & reapplied by the caller.
+fn reload_prefab_instance(
+    world: &mut World,
+    instance: Entity,
+    prefab_instance: &mut PrefabInstance,
+    prefab: &Prefab
+) {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+
+    // Despawn entities that are no longer in the prefab.
+    let source_entities = prefab.scene.entities.iter().map(|entity| entity.entity).collect::<HashSet<_>>();
+    let removed = prefab_instance.entity_map.iter().filter(|(source, spawned)| !source_entities.contains(source) && *spawned != instance).collect::<Vec<_>>();
+
+    for (source, spawned) in removed {
+        prefab_instance.entity_map.remove(source);
+        prefab_instance.components.remove(&source);
+
+        if world.get_entity(spawned).is_some() {
+            despawn_with_children_recursive(world, spawned);
+        }
+    }
+
+    // Entities that were despawned at runtime are spawned again, same as they would be for a fresh instance.
+    let despawned = prefab_instance.entity_map.iter().filter(|(_, spawned)| world.get_entity(*spawned).is_none()).map(|(source, _)| source).collect::<Vec<_>>();
+    for source in despawned {
+        prefab_instance.entity_map.remove(source);
+    }
+
+    let spawned_entities = prefab_instance.entity_map.values().collect::<HashSet<_>>();
+
+    // Remember the children we didn't spawn so they can be put back once the prefab is written.
+    let runtime_children = prefab_instance.entity_map.values().filter_map(|spawned| {
+        let children = world.get::<Children>(spawned)?.iter().filter(|child| !spawned_entities.contains(*child)).cloned().collect::<Vec<_>>();
+
+        (!children.is_empty()).then_some((spawned, children))
+    }).collect::<Vec<_>>();
+
+    // Writing the prefab applies its `Children` onto the existing ones element by element, which would leave runtime children's ids behind unmapped.
+    for (spawned, _) in &runtime_children {
+        world.entity_mut(*spawned).remove::<Children>();
+    }
+
+    // Remove components that are no longer in the prefab.
+    {
+        let type_registry = type_registry.read();
+
+        for entity in &prefab.scene.entities {
+            let Some(spawned) = prefab_instance.entity_map.get(entity.entity) else {
+                continue;
+            };
+            let Some(previous) = prefab_instance.components.get(&entity.entity) else {
+                continue;
+            };
+
+            let current = component_types(entity, &type_registry);
+            let mut entity_mut = world.entity_mut(spawned);
+
+            for type_id in previous.iter().filter(|type_id| !current.contains(type_id)) {
+                if let Some(reflect_component) = type_registry.get(*type_id).and_then(|registration| registration.data::<ReflectComponent>()) {
+                    reflect_component.remove(&mut entity_mut);
+                }
+            }
+        }
+    }
+
+    if let Err(err) = prefab.scene.write_to_world_with(world, &mut prefab_instance.entity_map, &type_registry) {
+        error!("Failed to reload prefab '{}' for {instance:?}: {err}", prefab.name);
+        return;
+    }
+
+    insert_derived_components(world, prefab_instance.entity_map.values());
+
+    for (parent, children) in runtime_children {
+        world.entity_mut(parent).push_children(&children);
+    }
+
+    for (source, spawned) in prefab_instance.entity_map.iter() {
+        world.entity_mut(spawned).insert(PrefabSource {
+            instance,
+            source
+        });
+    }
+
+    let type_registry = type_registry.read();
+    prefab_instance.components = prefab.scene.entities.iter().map(|entity| (entity.entity, component_types(entity, &type_registry))).collect();
+}
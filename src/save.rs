@@ -0,0 +1,115 @@
+use std::{path::{Path, PathBuf}, fs};
+
+use bevy::{prelude::*, asset::FileAssetIo, ecs::event::ManualEventReader};
+
+use crate::{PrefabResources, error::PrefabError, format::{append_extension, replace_file, serialize_prefab_file}, tree::PrefabLayout, extract::{ExtractOptions, extract_prefab}};
+
+/// Requests that the hierarchy under `root` is saved as a prefab at `path`, relative to the asset folder.
+///
+/// The file is written in the format its extension asks for, see [`crate::format::PrefabFormat::from_path`].
+#[derive(Event, Debug, Clone)]
+pub struct SavePrefabRequest {
+    pub root: Entity,
+    pub path: PathBuf,
+    pub name: String,
+}
+
+/// Sent when a [`SavePrefabRequest`] was written to disk.
+#[derive(Event, Debug, Clone)]
+pub struct PrefabSaved {
+    pub root: Entity,
+    pub path: PathBuf,
+}
+
+/// Sent when a [`SavePrefabRequest`] couldn't be saved.
+#[derive(Event, Debug)]
+pub struct PrefabSaveFailed {
+    pub root: Entity,
+    pub path: PathBuf,
+    pub error: PrefabError,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct PrefabSaveSettings {
+    /// Folder prefab paths are relative to, the [`AssetPlugin`]'s asset folder by default.
+    pub asset_folder: PathBuf,
+    /// Extension appended to the previous version of a file to keep it as a backup, `None` to not keep backups.
+    pub backup_extension: Option<String>,
+    /// Layout saved files are written in.
+    pub layout: PrefabLayout,
+}
+impl FromWorld for PrefabSaveSettings {
+    fn from_world(world: &mut World) -> Self {
+        // Only the file system backed asset io has a folder to write to, fall back to bevy's default one for the others.
+        let asset_folder = world.get_resource::<AssetServer>()
+            .and_then(|asset_server| asset_server.asset_io().downcast_ref::<FileAssetIo>().map(|asset_io| asset_io.root_path().clone()))
+            .unwrap_or_else(|| FileAssetIo::get_base_path().join("assets"));
+
+        Self {
+            asset_folder,
+            backup_extension: Some("bak".to_owned()),
+            layout: PrefabLayout::Flat
+        }
+    }
+}
+
+/// Saves the hierarchy under `root` as a prefab at `path`, relative to the asset folder.
+///
+/// The prefab is named after the file & written in the format its extension asks for.
+pub fn save_prefab(world: &World, root: Entity, path: impl AsRef<Path>) -> Result<(), PrefabError> {
+    let path = path.as_ref();
+    let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+    save_prefab_named(world, root, path, name)
+}
+
+fn save_prefab_named(world: &World, root: Entity, path: &Path, name: String) -> Result<(), PrefabError> {
+    let options = ExtractOptions::named(name)
+        .with_resource_filter(world.resource::<PrefabResources>().0.clone());
+
+    let settings = world.resource::<PrefabSaveSettings>();
+
+    let prefab = extract_prefab(world, root, &options)?;
+    let serialized_prefab = serialize_prefab_file(&prefab, path, settings.layout, world.resource::<AppTypeRegistry>())?;
+
+    let path = settings.asset_folder.join(path);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| PrefabError::io(parent, err))?;
+    }
+
+    if let Some(backup_extension) = &settings.backup_extension {
+        if path.exists() {
+            let backup_path = append_extension(&path, backup_extension);
+            fs::copy(&path, &backup_path).map_err(|err| PrefabError::io(&backup_path, err))?;
+        }
+    }
+
+    // Written next to the target & renamed over it, so a crash mid-write never leaves a half written prefab behind.
+    replace_file(&path, &serialized_prefab)
+}
+
+pub fn save_prefab_requests_system(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<SavePrefabRequest>>
+) {
+    let requests = reader.iter(world.resource::<Events<SavePrefabRequest>>()).cloned().collect::<Vec<_>>();
+
+    for SavePrefabRequest { root, path, name } in requests {
+        match save_prefab_named(world, root, &path, name) {
+            Ok(()) => world.send_event(PrefabSaved {
+                root,
+                path
+            }),
+            Err(error) => {
+                error!("Failed to save prefab to {}: {error}", path.display());
+
+                world.send_event(PrefabSaveFailed {
+                    root,
+                    path,
+                    error
+                });
+            }
+        }
+    }
+}
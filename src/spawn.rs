@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+
+use bevy::{prelude::*, ecs::{entity::EntityMap, system::EntityCommands}, asset::{LoadState, HandleId}, scene::SceneSpawnError};
+
+use crate::{Prefab, PrefabMarker, error::PrefabError, derived::insert_derived_components, instance::{PrefabInstance, PrefabSource, component_types}, overrides::{PrefabSnapshots, apply_prefab_overrides}};
+
+/// Placed on the root of a prefab instance until its [`Prefab`] has finished loading and been written into the world.
+#[derive(Component)]
+pub struct PendingPrefab {
+    pub handle: Handle<Prefab>,
+    /// Overrides the root transform stored in the prefab.
+    pub transform: Option<Transform>,
+}
+
+/// Sent when a [`PendingPrefab`] couldn't be spawned, its entity is left as is without the prefab's contents.
+#[derive(Event, Debug)]
+pub struct PrefabSpawnFailed {
+    pub instance: Entity,
+    pub error: PrefabError,
+}
+
+pub trait SpawnPrefabExt<'w, 's> {
+    /// Spawns an instance of `handle` once it has loaded.
+    ///
+    /// The returned entity becomes the prefab root, so components can be added to it right away.
+    fn spawn_prefab<'a>(&'a mut self, handle: Handle<Prefab>) -> EntityCommands<'w, 's, 'a>;
+
+    /// Same as [`SpawnPrefabExt::spawn_prefab`] but replaces the root transform and optionally parents the root under `parent`.
+    fn spawn_prefab_with<'a>(&'a mut self, handle: Handle<Prefab>, transform: Transform, parent: Option<Entity>) -> EntityCommands<'w, 's, 'a>;
+}
+
+impl<'w, 's> SpawnPrefabExt<'w, 's> for Commands<'w, 's> {
+    fn spawn_prefab<'a>(&'a mut self, handle: Handle<Prefab>) -> EntityCommands<'w, 's, 'a> {
+        self.spawn(PendingPrefab {
+            handle,
+            transform: None
+        })
+    }
+
+    fn spawn_prefab_with<'a>(&'a mut self, handle: Handle<Prefab>, transform: Transform, parent: Option<Entity>) -> EntityCommands<'w, 's, 'a> {
+        let mut entity_commands = self.spawn((
+            PendingPrefab {
+                handle,
+                transform: Some(transform)
+            },
+            TransformBundle::from_transform(transform)
+        ));
+
+        if let Some(parent) = parent {
+            entity_commands.set_parent(parent);
+        }
+
+        entity_commands
+    }
+}
+
+/// Writes every [`PendingPrefab`] whose asset, and the assets of any prefabs nested in it, have finished loading into the world.
+pub fn spawn_pending_prefabs_system(
+    world: &mut World
+) {
+    let pending = {
+        let mut query = world.query::<(Entity, &PendingPrefab)>();
+
+        query.iter(world).map(|(entity, pending)| (entity, pending.handle.clone_weak(), pending.transform)).collect::<Vec<_>>()
+    };
+
+    if pending.is_empty() {
+        return;
+    }
+
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+
+    world.resource_scope(|world, prefabs: Mut<Assets<Prefab>>| {
+        for (instance, weak_handle, transform) in pending {
+            match is_loaded(&prefabs, world.resource::<AssetServer>(), &weak_handle, &mut Vec::new()) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(error) => {
+                    warn!("Prefab for {instance:?} will not be spawned: {error}");
+                    world.entity_mut(instance).remove::<PendingPrefab>();
+                    world.send_event(PrefabSpawnFailed {
+                        instance,
+                        error
+                    });
+                    continue;
+                }
+            }
+
+            let Some(PendingPrefab { handle, .. }) = world.entity_mut(instance).take::<PendingPrefab>() else {
+                continue;
+            };
+
+            if let Err(error) = write_prefab(world, &prefabs, handle, instance, &type_registry, &mut Vec::new()) {
+                error!("Failed to spawn prefab for {instance:?}: {error}");
+                world.send_event(PrefabSpawnFailed {
+                    instance,
+                    error
+                });
+                continue;
+            }
+
+            if let Some(transform) = transform {
+                world.entity_mut(instance).insert(transform);
+            }
+        }
+    });
+}
+
+fn handle_path(asset_server: &AssetServer, handle: HandleId) -> Option<PathBuf> {
+    asset_server.get_handle_path(handle).map(|path| path.path().to_path_buf())
+}
+
+/// The error for `handle` being reached again while it's on `stack`.
+fn cycle_error(asset_server: &AssetServer, stack: &[HandleId], handle: HandleId) -> PrefabError {
+    PrefabError::Cycle {
+        chain: stack.iter().chain([&handle]).filter_map(|id| handle_path(asset_server, *id)).collect()
+    }
+}
+
+/// Whether `handle` and every prefab nested in it are loaded.
+///
+/// Errors if any of them failed to load or a prefab (indirectly) contains itself.
+fn is_loaded(prefabs: &Assets<Prefab>, asset_server: &AssetServer, handle: &Handle<Prefab>, stack: &mut Vec<HandleId>) -> Result<bool, PrefabError> {
+    if stack.contains(&handle.id()) {
+        return Err(cycle_error(asset_server, stack, handle.id()));
+    }
+
+    let Some(prefab) = prefabs.get(handle) else {
+        return match asset_server.get_load_state(handle) {
+            LoadState::Failed => Err(PrefabError::NotLoaded {
+                path: handle_path(asset_server, handle.id())
+            }),
+            _ => Ok(false)
+        };
+    };
+
+    stack.push(handle.id());
+    for nested in &prefab.nested {
+        if !is_loaded(prefabs, asset_server, &nested.handle, stack)? {
+            return Ok(false);
+        }
+    }
+    stack.pop();
+
+    Ok(true)
+}
+
+/// Writes the prefab `handle` into the world with its root mapped onto `instance`, expanding any prefabs nested in it.
+///
+/// `stack` holds the prefabs currently being written, to catch prefabs that (indirectly) contain themselves.
+fn write_prefab(world: &mut World, prefabs: &Assets<Prefab>, handle: Handle<Prefab>, instance: Entity, type_registry: &AppTypeRegistry, stack: &mut Vec<HandleId>) -> Result<(), PrefabError> {
+    let path = handle_path(world.resource::<AssetServer>(), handle.id());
+
+    if stack.contains(&handle.id()) {
+        return Err(cycle_error(world.resource::<AssetServer>(), stack, handle.id()));
+    }
+
+    let prefab = prefabs.get(&handle).ok_or_else(|| PrefabError::NotLoaded {
+        path: path.clone()
+    })?;
+    let root = prefab.root().ok_or_else(|| PrefabError::InvalidEntity {
+        path: path.clone(),
+        entity: None,
+        reason: format!("prefab '{}' has no root entity", prefab.name)
+    })?;
+
+    // Map the prefab root onto the entity we already handed out, every other entity gets a fresh one.
+    let mut entity_map = EntityMap::default();
+    entity_map.insert(root, instance);
+
+    prefab.scene.write_to_world_with(world, &mut entity_map, type_registry).map_err(|err| match err {
+        SceneSpawnError::UnregisteredComponent { type_name }
+        | SceneSpawnError::UnregisteredResource { type_name }
+        | SceneSpawnError::UnregisteredType { type_name } => PrefabError::UnknownType {
+            path,
+            position: None,
+            type_path: type_name
+        },
+        source => PrefabError::Spawn {
+            path,
+            source
+        }
+    })?;
+
+    insert_derived_components(world, entity_map.values());
+
+    for (source, spawned) in entity_map.iter() {
+        world.entity_mut(spawned).insert(PrefabSource {
+            instance,
+            source
+        });
+    }
+
+    if world.resource::<PrefabSnapshots>().get(&handle).is_none() {
+        world.resource_mut::<PrefabSnapshots>().insert(&handle, &prefab.scene);
+    }
+
+    let components = {
+        let type_registry = type_registry.read();
+
+        prefab.scene.entities.iter().map(|entity| (entity.entity, component_types(entity, &type_registry))).collect()
+    };
+
+    let nested_instances = prefab.nested.iter().filter_map(|nested| Some((entity_map.get(nested.entity)?, nested))).collect::<Vec<_>>();
+
+    world.entity_mut(instance).insert((
+        PrefabInstance {
+            handle: handle.clone(),
+            entity_map,
+            components
+        },
+        PrefabMarker
+    ));
+
+    stack.push(handle.id());
+    for (nested_instance, nested) in nested_instances {
+        write_prefab(world, prefabs, nested.handle.clone(), nested_instance, type_registry, stack)?;
+        apply_prefab_overrides(world, nested_instance, &nested.overrides);
+    }
+    stack.pop();
+
+    Ok(())
+}
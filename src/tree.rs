@@ -0,0 +1,440 @@
+use bevy::{prelude::*, reflect::{DynamicList, DynamicTupleStruct, TypeRegistryArc, TypeRegistryInternal, Typed, serde::TypedReflectSerializer}, scene::DynamicEntity, utils::{HashMap, HashSet}};
+use serde::{Serialize, Deserialize, de::{DeserializeSeed, Visitor, MapAccess, SeqAccess}, ser::{SerializeStruct, SerializeMap, SerializeSeq}};
+
+use crate::{Prefab, error::{PrefabError, DeserializeErrors}, lenient::{PrefabLoadMode, ComponentsDeserializer, SkippedComponent}, canonical::{children_of, entities_by_id, hierarchy_order}, migration::versioned_type_path, extract::renumber_entities};
+#[cfg(feature = "ron")]
+use {std::path::Path, crate::{deserialize_prefab, serialize_prefab_as, format::FormatMigrations, nested::check_nested_entities}};
+
+pub const TREE_NODE_STRUCT: &str = "Entity";
+pub const TREE_NODE_COMPONENTS: &str = "components";
+pub const TREE_NODE_CHILDREN: &str = "children";
+pub const TREE_NODE_FIELDS: &[&str] = &[TREE_NODE_COMPONENTS, TREE_NODE_CHILDREN];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum TreeNodeField {
+    Components,
+    Children,
+}
+
+/// How the entities of a prefab are laid out in its file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrefabLayout {
+    /// A `scene` map of entity ids to components, with the hierarchy stored in [`Parent`] & [`Children`] components. Mirrors [`DynamicScene`].
+    #[default]
+    Flat,
+    /// A `root` entity, with each entity listing its components & its `children`. Meant for hand authoring.
+    ///
+    /// Entity ids are implied by the position in the tree: the root is `0`, then counting up depth-first.
+    /// Variants can't use it, as their ids refer to entities of their base.
+    Tree,
+}
+
+/// An entity of a tree shaped prefab, before it's been given an id.
+pub struct TreeNode {
+    pub components: Vec<Box<dyn Reflect>>,
+    pub children: Vec<TreeNode>,
+    /// Components left out in [`PrefabLoadMode::Lenient`], without their entity.
+    pub skipped: Vec<SkippedComponent>,
+}
+
+/// Flattens a tree into scene entities, numbering them depth-first & rebuilding their [`Parent`] & [`Children`] components.
+///
+/// Also gives the components that were skipped while loading the tree.
+pub fn tree_entities(root: TreeNode) -> (Vec<DynamicEntity>, Vec<SkippedComponent>) {
+    let mut entities = Vec::new();
+    let mut skipped = Vec::new();
+    push_tree_node(root, None, &mut entities, &mut skipped);
+
+    (entities, skipped)
+}
+
+fn push_tree_node(node: TreeNode, parent: Option<Entity>, entities: &mut Vec<DynamicEntity>, skipped: &mut Vec<SkippedComponent>) -> Entity {
+    let entity = Entity::from_raw(entities.len() as u32);
+    let index = entities.len();
+
+    skipped.extend(node.skipped.into_iter().map(|skipped| SkippedComponent {
+        entity: Some(entity),
+        ..skipped
+    }));
+
+    entities.push(DynamicEntity {
+        entity,
+        components: node.components
+    });
+
+    if let Some(parent) = parent {
+        entities[index].components.push(parent_component(parent));
+    }
+
+    let children = node.children.into_iter()
+        .map(|child| push_tree_node(child, Some(entity), entities, skipped))
+        .collect::<Vec<_>>();
+
+    if !children.is_empty() {
+        entities[index].components.push(children_component(children));
+    }
+
+    entity
+}
+
+// Neither can be constructed with entities outside of bevy_hierarchy, so they're built as dynamic values & applied on spawn like any other saved component.
+fn parent_component(parent: Entity) -> Box<dyn Reflect> {
+    let mut component = DynamicTupleStruct::default();
+    component.set_represented_type(Some(<Parent as Typed>::type_info()));
+    component.insert(parent);
+
+    Box::new(component)
+}
+
+pub(crate) fn children_component(children: Vec<Entity>) -> Box<dyn Reflect> {
+    let mut list = DynamicList::default();
+    for child in children {
+        list.push(child);
+    }
+
+    let mut component = DynamicTupleStruct::default();
+    component.set_represented_type(Some(<Children as Typed>::type_info()));
+    component.insert(list);
+
+    Box::new(component)
+}
+
+fn is_hierarchy_component(component: &dyn Reflect) -> bool {
+    component.type_name() == std::any::type_name::<Parent>() || component.type_name() == std::any::type_name::<Children>()
+}
+
+/// Gives the entities of `prefab` the ids a tree shaped file implies, their position in [`hierarchy_order`].
+///
+/// Does nothing to variants, whose ids have to match their base. Fails if a component references an entity that isn't in the prefab.
+pub fn renumber_in_hierarchy_order(prefab: &mut Prefab, type_registry: &TypeRegistryInternal) -> Result<(), PrefabError> {
+    if prefab.variant.is_some() {
+        return Ok(());
+    }
+
+    let local_ids = hierarchy_order(prefab).into_iter()
+        .enumerate()
+        .map(|(index, entity)| (entity.entity, Entity::from_raw(index as u32)))
+        .collect::<HashMap<_, _>>();
+
+    renumber_entities(&mut prefab.scene, &mut prefab.nested, &local_ids, type_registry)
+}
+
+/// Checks that `prefab` can be written as a tree without losing anything or changing its entity ids.
+fn check_tree_layout(prefab: &Prefab) -> Result<(), String> {
+    if prefab.variant.is_some() {
+        return Err("variants can't be written as a tree".to_owned());
+    }
+    if prefab.root().is_none() {
+        return Err("prefab has no root entity".to_owned());
+    }
+
+    let order = hierarchy_order(prefab);
+    let entities = entities_by_id(order.iter().copied());
+    let mut visited = HashSet::new();
+    let mut entities_to_visit = prefab.root().into_iter().collect::<Vec<_>>();
+    while let Some(entity) = entities_to_visit.pop() {
+        if let Some(dynamic_entity) = entities.get(&entity) {
+            if visited.insert(entity) {
+                entities_to_visit.extend(children_of(dynamic_entity));
+            }
+        }
+    }
+
+    if let Some(unreachable) = order.iter().find(|entity| !visited.contains(&entity.entity)) {
+        return Err(format!("{:?} isn't below the root", unreachable.entity));
+    }
+    if let Some((index, entity)) = order.iter().enumerate().find(|(index, entity)| entity.entity.index() as usize != *index) {
+        return Err(format!("{:?} would become entity {index}, renumber the prefab in hierarchy order first", entity.entity));
+    }
+
+    Ok(())
+}
+
+/// Rewrites the contents of a `.prefab` file in `layout`, e.g. to turn a saved prefab into one that's easier to edit by hand.
+#[cfg(feature = "ron")]
+pub fn convert_layout(bytes: &[u8], path: &Path, layout: PrefabLayout, type_registry: &TypeRegistryArc, migrations: &FormatMigrations) -> Result<String, PrefabError> {
+    let mut prefab = deserialize_prefab(bytes, path, type_registry, PrefabLoadMode::Strict, migrations)?;
+
+    if layout == PrefabLayout::Tree {
+        if prefab.variant.is_some() {
+            return Err(PrefabError::Serialize(format!("{} is a variant, which can't be written as a tree", path.to_string_lossy())));
+        }
+
+        check_nested_entities(&prefab, path)?;
+        renumber_in_hierarchy_order(&mut prefab, &type_registry.read())?;
+    }
+
+    serialize_prefab_as(&prefab, type_registry, layout).map_err(|err| PrefabError::Serialize(err.to_string()))
+}
+
+/// Writes the prefab's root & everything below it as nested entities.
+pub struct TreeSerializer<'a> {
+    pub prefab: &'a Prefab,
+    pub registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for TreeSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        check_tree_layout(self.prefab).map_err(serde::ser::Error::custom)?;
+
+        let entities = entities_by_id(&self.prefab.scene.entities);
+        let root = self.prefab.root()
+            .and_then(|root| entities.get(&root).copied())
+            .ok_or_else(|| serde::ser::Error::custom("prefab has no root entity"))?;
+
+        TreeNodeSerializer {
+            entities: &entities,
+            entity: root,
+            registry: self.registry
+        }.serialize(serializer)
+    }
+}
+
+struct TreeNodeSerializer<'a> {
+    entities: &'a HashMap<Entity, &'a DynamicEntity>,
+    entity: &'a DynamicEntity,
+    registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for TreeNodeSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let children = children_of(self.entity).into_iter()
+            .filter_map(|child| self.entities.get(&child).copied())
+            .collect::<Vec<_>>();
+
+        let mut state = serializer.serialize_struct(TREE_NODE_STRUCT, TREE_NODE_FIELDS.len())?;
+
+        state.serialize_field(
+            TREE_NODE_COMPONENTS,
+            &TreeComponentsSerializer {
+                components: &self.entity.components,
+                registry: self.registry
+            }
+        )?;
+
+        if children.is_empty() {
+            state.skip_field(TREE_NODE_CHILDREN)?;
+        } else {
+            state.serialize_field(
+                TREE_NODE_CHILDREN,
+                &TreeChildrenSerializer {
+                    entities: self.entities,
+                    children: &children,
+                    registry: self.registry
+                }
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+/// Components sorted by type path, leaving out [`Parent`] & [`Children`] as the tree already describes them.
+struct TreeComponentsSerializer<'a> {
+    components: &'a [Box<dyn Reflect>],
+    registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for TreeComponentsSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut components = self.components.iter()
+            .map(|component| &**component)
+            .filter(|component| !is_hierarchy_component(*component))
+            .collect::<Vec<_>>();
+        components.sort_by(|a, b| a.type_name().cmp(b.type_name()));
+
+        let registry = self.registry.read();
+        let mut state = serializer.serialize_map(Some(components.len()))?;
+
+        for component in components {
+            state.serialize_entry(
+                &versioned_type_path(component, &registry),
+                &TypedReflectSerializer::new(component, &registry)
+            )?;
+        }
+
+        state.end()
+    }
+}
+
+struct TreeChildrenSerializer<'a> {
+    entities: &'a HashMap<Entity, &'a DynamicEntity>,
+    children: &'a [&'a DynamicEntity],
+    registry: &'a TypeRegistryArc,
+}
+impl<'a> Serialize for TreeChildrenSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+
+        let mut state = serializer.serialize_seq(Some(self.children.len()))?;
+
+        for child in self.children {
+            state.serialize_element(&TreeNodeSerializer {
+                entities: self.entities,
+                entity: child,
+                registry: self.registry
+            })?;
+        }
+
+        state.end()
+    }
+}
+
+pub struct TreeNodeDeserializer<'a> {
+    pub type_registry: &'a TypeRegistryInternal,
+    pub mode: PrefabLoadMode,
+    pub errors: &'a DeserializeErrors,
+}
+impl<'a, 'de> DeserializeSeed<'de> for TreeNodeDeserializer<'a> {
+    type Value = TreeNode;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            TREE_NODE_STRUCT,
+            TREE_NODE_FIELDS,
+            TreeNodeVisitor {
+                type_registry: self.type_registry,
+                mode: self.mode,
+                errors: self.errors
+            }
+        )
+    }
+}
+
+struct TreeNodeVisitor<'a> {
+    type_registry: &'a TypeRegistryInternal,
+    mode: PrefabLoadMode,
+    errors: &'a DeserializeErrors,
+}
+impl<'a> TreeNodeVisitor<'a> {
+    fn components(&self) -> ComponentsDeserializer<'a> {
+        ComponentsDeserializer {
+            type_registry: self.type_registry,
+            mode: self.mode,
+            resources: false,
+            entity: None,
+            errors: self.errors
+        }
+    }
+
+    fn children(&self) -> TreeChildrenDeserializer<'a> {
+        TreeChildrenDeserializer {
+            type_registry: self.type_registry,
+            mode: self.mode,
+            errors: self.errors
+        }
+    }
+}
+impl<'a, 'de> Visitor<'de> for TreeNodeVisitor<'a> {
+    type Value = TreeNode;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("Entity Struct")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let (components, skipped) = seq.next_element_seed(self.components())?.ok_or_else(|| self.errors.missing_field(TREE_NODE_COMPONENTS))?;
+
+        let children = seq.next_element_seed(self.children())?.unwrap_or_default();
+
+        Ok(TreeNode {
+            components,
+            children,
+            skipped
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>, {
+
+        let mut components = None;
+        let mut children = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                TreeNodeField::Components => {
+                    if components.is_some() {
+                        return Err(serde::de::Error::duplicate_field(TREE_NODE_COMPONENTS));
+                    }
+
+                    components = Some(map.next_value_seed(self.components())?);
+                }
+                TreeNodeField::Children => {
+                    if children.is_some() {
+                        return Err(serde::de::Error::duplicate_field(TREE_NODE_CHILDREN));
+                    }
+
+                    children = Some(map.next_value_seed(self.children())?);
+                }
+            }
+        }
+
+        let (components, skipped): (Vec<Box<dyn Reflect>>, _) = components.ok_or_else(|| self.errors.missing_field(TREE_NODE_COMPONENTS))?;
+
+        // The hierarchy comes from the tree, stray hierarchy components would contradict it.
+        if let Some(component) = components.iter().find(|component| is_hierarchy_component(&***component)) {
+            return Err(serde::de::Error::custom(format_args!("`{}` is implied by `{TREE_NODE_CHILDREN}` & can't be written out", component.type_name())));
+        }
+
+        Ok(TreeNode {
+            components,
+            children: children.unwrap_or_default(),
+            skipped
+        })
+    }
+}
+
+struct TreeChildrenDeserializer<'a> {
+    type_registry: &'a TypeRegistryInternal,
+    mode: PrefabLoadMode,
+    errors: &'a DeserializeErrors,
+}
+impl<'a, 'de> DeserializeSeed<'de> for TreeChildrenDeserializer<'a> {
+    type Value = Vec<TreeNode>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+impl<'a, 'de> Visitor<'de> for TreeChildrenDeserializer<'a> {
+    type Value = Vec<TreeNode>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("sequence of entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>, {
+
+        let mut children = Vec::new();
+
+        while let Some(child) = seq.next_element_seed(TreeNodeDeserializer {
+            type_registry: self.type_registry,
+            mode: self.mode,
+            errors: self.errors
+        })? {
+            children.push(child);
+        }
+
+        Ok(children)
+    }
+}
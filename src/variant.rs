@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{prelude::*, asset::LoadContext, reflect::{GetPath, TypeRegistryArc}, scene::DynamicEntity, utils::HashSet};
+
+use crate::{Prefab, deserialize_prefab, error::PrefabError, format::FormatMigrations, lenient::PrefabLoadMode, extract::remove_children, overrides::PrefabOverride, canonical::children_of, tree::children_component};
+
+/// The parts of a variant prefab describing how it differs from its base.
+///
+/// Next to these a variant's scene holds the entities it adds, and components it adds or replaces on entities of its base (by using the same entity ids).
+/// Added entities need ids the base doesn't use, an entity whose [`Parent`] differs from the base entity with its id is rejected.
+#[derive(Debug)]
+pub struct PrefabVariant {
+    /// Asset path of the prefab this is a variant of.
+    pub base: String,
+    /// Field level changes to entities of the base.
+    pub overrides: Vec<PrefabOverride>,
+    /// Entities of the base, along with their descendants, that aren't part of the variant.
+    pub removed: Vec<Entity>,
+}
+
+/// Flattens `prefab` onto its chain of bases, if it's a variant.
+///
+/// Bases are read through `load_context`, so changes to any of them hot reload the variant too.
+pub async fn resolve_variant(prefab: Prefab, load_context: &LoadContext<'_>, type_registry: &TypeRegistryArc, mode: PrefabLoadMode, migrations: &FormatMigrations) -> Result<Prefab, PrefabError> {
+    let mut chain = vec![load_context.path().to_path_buf()];
+    let mut variants = Vec::new();
+    let mut prefab = prefab;
+
+    while let Some(variant) = prefab.variant.take() {
+        let base_path = PathBuf::from(&variant.base);
+
+        if chain.contains(&base_path) {
+            chain.push(base_path);
+            return Err(PrefabError::Cycle {
+                chain
+            });
+        }
+
+        let bytes = load_context.read_asset_bytes(&base_path).await
+            .map_err(|err| PrefabError::io(&base_path, std::io::Error::other(err)))?;
+        let base = deserialize_prefab(&bytes, &base_path, type_registry, mode, migrations)?;
+
+        variants.push((chain[chain.len() - 1].clone(), prefab, variant));
+        chain.push(base_path);
+        prefab = base;
+    }
+
+    let mut prefab = flatten_variants(prefab, variants, type_registry)?;
+    prefab.bases = chain.into_iter().skip(1).map(|path| path.to_string_lossy().into_owned()).collect();
+
+    Ok(prefab)
+}
+
+/// Applies `variants`, each the file it was read from along with its prefab & variant fields, on top of `base`.
+///
+/// `variants` are ordered from the outermost variant to the one closest to `base`.
+pub(crate) fn flatten_variants(base: Prefab, variants: Vec<(PathBuf, Prefab, PrefabVariant)>, type_registry: &TypeRegistryArc) -> Result<Prefab, PrefabError> {
+    variants.into_iter().rev().try_fold(base, |base, (path, variant_prefab, variant)| {
+        flatten_variant(base, variant_prefab, variant, &path, type_registry)
+    })
+}
+
+/// Applies a variant's changes to its (already flattened) base, `path` is the variant's file.
+fn flatten_variant(mut base: Prefab, variant_prefab: Prefab, variant: PrefabVariant, path: &Path, type_registry: &TypeRegistryArc) -> Result<Prefab, PrefabError> {
+    base.name = variant_prefab.name;
+    base.report.skipped.extend(variant_prefab.report.skipped);
+
+    // Removing an entity removes everything below it as well.
+    let mut removed = variant.removed.into_iter().collect::<HashSet<_>>();
+    loop {
+        let children = base.scene.entities.iter()
+            .filter(|entity| !removed.contains(&entity.entity) && parent_of(entity).is_some_and(|parent| removed.contains(&parent)))
+            .map(|entity| entity.entity)
+            .collect::<Vec<_>>();
+
+        if children.is_empty() {
+            break;
+        }
+
+        removed.extend(children);
+    }
+
+    base.scene.entities.retain(|entity| !removed.contains(&entity.entity));
+    base.nested.retain(|nested| !removed.contains(&nested.entity));
+    for entity in &mut base.scene.entities {
+        remove_children(entity, &removed);
+    }
+
+    let base_ids = base.scene.entities.iter().map(|entity| entity.entity).collect::<HashSet<_>>();
+    let mut added_children = Vec::new();
+
+    for entity in variant_prefab.scene.entities {
+        match base.scene.entities.iter_mut().find(|base_entity| base_entity.entity == entity.entity) {
+            Some(base_entity) => {
+                // An entity under another parent is one the variant adds, not a change to the base entity that happens to share its id.
+                let parent = parent_of(&entity);
+                if parent.is_some() && parent != parent_of(base_entity) {
+                    return Err(PrefabError::InvalidEntity {
+                        path: Some(path.to_path_buf()),
+                        entity: Some(entity.entity),
+                        reason: "is added by the variant, but its base already has an entity with that id".to_owned()
+                    });
+                }
+
+                for component in entity.components {
+                    match base_entity.components.iter_mut().find(|base_component| base_component.type_name() == component.type_name()) {
+                        Some(base_component) => *base_component = component,
+                        None => base_entity.components.push(component)
+                    }
+                }
+            }
+            None => {
+                if let Some(parent) = parent_of(&entity).filter(|parent| base_ids.contains(parent)) {
+                    added_children.push((parent, entity.entity));
+                }
+                base.scene.entities.push(entity);
+            }
+        }
+    }
+
+    // Entities added below entities of the base have to be listed in their parent's `Children` too.
+    for (parent, child) in added_children {
+        let Some(parent) = base.scene.entities.iter_mut().find(|entity| entity.entity == parent) else {
+            continue;
+        };
+
+        let mut children = children_of(parent);
+        if children.contains(&child) {
+            continue;
+        }
+        children.push(child);
+
+        parent.components.retain(|component| component.type_name() != std::any::type_name::<Children>());
+        parent.components.push(children_component(children));
+    }
+
+    for resource in variant_prefab.scene.resources {
+        match base.scene.resources.iter_mut().find(|base_resource| base_resource.type_name() == resource.type_name()) {
+            Some(base_resource) => *base_resource = resource,
+            None => base.scene.resources.push(resource)
+        }
+    }
+
+    base.nested.retain(|nested| !variant_prefab.nested.iter().any(|variant_nested| variant_nested.entity == nested.entity));
+    base.nested.extend(variant_prefab.nested);
+
+    for prefab_override in variant.overrides {
+        apply_override(&mut base.scene.entities, prefab_override, type_registry);
+    }
+
+    Ok(base)
+}
+
+fn parent_of(entity: &DynamicEntity) -> Option<Entity> {
+    entity.components.iter()
+        .find(|component| component.type_name() == std::any::type_name::<Parent>())
+        .and_then(|parent| <Parent as FromReflect>::from_reflect(&**parent))
+        .map(|parent| parent.get())
+}
+
+fn apply_override(entities: &mut [DynamicEntity], prefab_override: PrefabOverride, type_registry: &TypeRegistryArc) {
+    let Some(entity) = entities.iter_mut().find(|entity| entity.entity == prefab_override.source) else {
+        warn!("Variant overrides {:?} which isn't in its base.", prefab_override.source);
+        return;
+    };
+
+    let Some(value) = prefab_override.value else {
+        entity.components.retain(|component| component.type_name() != prefab_override.component);
+        return;
+    };
+
+    let component = entity.components.iter_mut().find(|component| component.type_name() == prefab_override.component);
+
+    match (component, prefab_override.path.is_empty()) {
+        (Some(component), true) => *component = value,
+        (None, true) => entity.components.push(value),
+        (Some(component), false) => match component.as_mut().reflect_path_mut(&prefab_override.path) {
+            Ok(field) => field.apply(&*value),
+            Err(err) => warn!("Couldn't apply variant override '{}{}': {err}", prefab_override.component, prefab_override.path)
+        },
+        (None, false) => {
+            // Overriding a field of a component the base doesn't have, start from the component's default.
+            let type_registry = type_registry.read();
+            let Some(mut component) = type_registry.get_with_name(&prefab_override.component)
+                .and_then(|registration| registration.data::<ReflectDefault>())
+                .map(|reflect_default| reflect_default.default()) else {
+                warn!("Variant overrides '{}{}' on {:?}, which doesn't have that component.", prefab_override.component, prefab_override.path, prefab_override.source);
+                return;
+            };
+
+            let applied = match component.as_mut().reflect_path_mut(&prefab_override.path) {
+                Ok(field) => {
+                    field.apply(&*value);
+                    true
+                }
+                Err(err) => {
+                    warn!("Couldn't apply variant override '{}{}': {err}", prefab_override.component, prefab_override.path);
+                    false
+                }
+            };
+
+            if applied {
+                entity.components.push(component);
+            }
+        }
+    }
+}
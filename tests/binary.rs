@@ -0,0 +1,16 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_scene_test::{binary::serialize_binary_prefab, lenient::PrefabLoadMode};
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+#[test]
+fn round_trips_through_binary() {
+    let app = common::app();
+    let prefab = common::deserialize(&app, "demo.prefab", FIXTURE.as_bytes(), PrefabLoadMode::Strict).unwrap();
+    let bytes = serialize_binary_prefab(&prefab, app.world.resource::<AppTypeRegistry>()).unwrap();
+    let prefab = common::deserialize(&app, "demo.prefab.bin", &bytes, PrefabLoadMode::Strict).unwrap();
+
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use bevy_scene_test::{canonical::canonicalize, lenient::PrefabLoadMode};
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+/// `demo.prefab` with its entities & components out of order.
+const SHUFFLED: &str = r#"(
+  name: "Test",
+  scene: {
+    1: (
+      components: {
+        "bevy_transform::components::transform::Transform": (
+          translation: (x: 1.0, y: 0.5, z: -1.3),
+          rotation: (0.0, 0.0, 0.0, 1.0),
+          scale: (x: 1.0, y: 1.0, z: 1.0),
+        ),
+        "tests::TestComponent": (name: "Stove"),
+        "bevy_scene_test::LeafNode": (),
+        "bevy_hierarchy::components::parent::Parent": (0),
+      },
+    ),
+    0: (
+      components: {
+        "bevy_transform::components::transform::Transform": (
+          translation: (x: 0.0, y: 0.0, z: 0.0),
+          rotation: (0.0, 0.0, 0.0, 1.0),
+          scale: (x: 1.0, y: 1.0, z: 1.0),
+        ),
+        "tests::TestComponent": (name: "Steve"),
+        "bevy_hierarchy::components::children::Children": ([1]),
+      },
+    ),
+  },
+  version: 1,
+)"#;
+
+/// A root whose children are listed in the opposite order of their ids.
+const REVERSED_CHILDREN: &str = r#"(
+  version: 1,
+  name: "Reversed",
+  scene: {
+    1: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (3),
+      },
+    ),
+    2: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (3),
+      },
+    ),
+    3: (
+      components: {
+        "bevy_hierarchy::components::children::Children": ([2, 1]),
+      },
+    ),
+  },
+)"#;
+
+#[test]
+fn saves_shuffled_prefabs_canonically() {
+    let app = common::app();
+    let prefab = common::deserialize(&app, "shuffled.prefab", SHUFFLED.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
+
+#[test]
+fn orders_entities_by_hierarchy() {
+    let app = common::app();
+    let mut prefab = common::deserialize(&app, "reversed.prefab", REVERSED_CHILDREN.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+    canonicalize(&mut prefab);
+
+    let entities = prefab.scene.entities.iter().map(|entity| entity.entity.index()).collect::<Vec<_>>();
+    assert_eq!(entities, vec![3, 2, 1]);
+}
@@ -0,0 +1,130 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+/// A prefab only using types the `prefab` tool knows, written the way the serializer writes it.
+const VALID: &str = r#"(
+  version: 1,
+  name: "Valid",
+  scene: {
+    0: (
+      components: {
+        "bevy_transform::components::transform::Transform": (
+          translation: (
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+          ),
+          rotation: (0.0, 0.0, 0.0, 1.0),
+          scale: (
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+          ),
+        ),
+      },
+    ),
+  },
+)"#;
+
+/// A root with a single child, the base of the variants below.
+const BASE: &str = r#"(
+  version: 1,
+  name: "Base",
+  scene: {
+    0: (
+      components: {
+        "bevy_hierarchy::components::children::Children": ([
+          1,
+        ]),
+      },
+    ),
+    1: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (0),
+      },
+    ),
+  },
+)"#;
+
+/// Adds entity 2 under the root & moves the base's entity 1 under it, same as `tests/assets/colliding_variant.prefab`.
+const COLLIDING_VARIANT: &str = r#"(
+  version: 1,
+  name: "Colliding",
+  scene: {
+    1: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (2),
+      },
+    ),
+    2: (
+      components: {
+        "bevy_hierarchy::components::children::Children": ([
+          1,
+        ]),
+        "bevy_hierarchy::components::parent::Parent": (0),
+      },
+    ),
+  },
+  base: "base.prefab",
+)"#;
+
+/// Runs the `prefab` tool from `folder`, returning its exit code.
+fn prefab(folder: &Path, args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_prefab"))
+        .args(args)
+        .current_dir(folder)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+/// An empty folder for `test` to write its prefabs to, with an `assets` folder inside.
+fn folder(test: &str) -> PathBuf {
+    let folder = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli").join(test);
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(folder.join("assets")).unwrap();
+
+    folder
+}
+
+#[test]
+fn succeeds_on_valid_prefabs() {
+    let folder = folder("valid");
+    fs::write(folder.join("assets/valid.prefab"), VALID).unwrap();
+
+    assert_eq!(prefab(&folder, &["validate", "assets/valid.prefab"]), Some(0));
+    assert_eq!(prefab(&folder, &["fmt", "--check", "assets/valid.prefab"]), Some(0));
+}
+
+#[test]
+fn fails_on_broken_prefabs() {
+    let folder = folder("broken");
+
+    fs::write(folder.join("assets/broken.prefab"), "(\n  version: 1,\n  name: \"Broken\",\n  scene: {").unwrap();
+    assert_eq!(prefab(&folder, &["validate", "assets/broken.prefab"]), Some(1));
+
+    // Nested prefabs & bases have to exist in the assets folder.
+    fs::write(folder.join("assets/orphan.prefab"), "(\n  version: 1,\n  name: \"Orphan\",\n  scene: {},\n  base: \"missing.prefab\",\n)").unwrap();
+    assert_eq!(prefab(&folder, &["validate", "assets/orphan.prefab"]), Some(1));
+}
+
+#[test]
+fn validates_variants_flattened_onto_their_base() {
+    let folder = folder("variant");
+    fs::write(folder.join("assets/base.prefab"), BASE).unwrap();
+
+    fs::write(folder.join("assets/variant.prefab"), "(\n  version: 1,\n  name: \"Variant\",\n  scene: {},\n  base: \"base.prefab\",\n  removed: [1],\n)").unwrap();
+    assert_eq!(prefab(&folder, &["validate", "assets/variant.prefab"]), Some(0));
+
+    fs::write(folder.join("assets/colliding_variant.prefab"), COLLIDING_VARIANT).unwrap();
+    assert_eq!(prefab(&folder, &["validate", "assets/colliding_variant.prefab"]), Some(1));
+}
+
+#[test]
+fn rejects_bad_usage() {
+    let folder = folder("usage");
+
+    assert_eq!(prefab(&folder, &[]), Some(2));
+    assert_eq!(prefab(&folder, &["validate"]), Some(2));
+    assert_eq!(prefab(&folder, &["convert", "--to", "yaml", "assets/valid.prefab"]), Some(2));
+}
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use bevy::{prelude::*, asset::LoadState, reflect::GetTypeRegistration};
+use bevy_scene_test::{Prefab, PrefabPlugin, LeafNode, deserialize_prefab, serialize_prefab, error::PrefabError, format::FormatMigrations, lenient::PrefabLoadMode, migration::{PrefabAliases, ReflectPrefabAliases}};
+
+/// The demo's component. Its type path differs between test crates, so prefabs in `tests/assets` refer to it by its alias.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, PrefabAliases)]
+pub struct TestComponent {
+    pub name: String
+}
+impl PrefabAliases for TestComponent {
+    const ALIASES: &'static [&'static str] = &[TEST_COMPONENT_ALIAS];
+}
+
+pub const TEST_COMPONENT_ALIAS: &str = "tests::TestComponent";
+
+/// A headless app loading prefabs from `tests/assets`.
+pub fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            asset_folder: "tests/assets".to_owned(),
+            ..default()
+        },
+        TransformPlugin,
+        HierarchyPlugin,
+        PrefabPlugin
+    ))
+    .register_type::<TestComponent>();
+
+    app
+}
+
+/// Spawns the same hierarchy as the demo's `spawn_world_system`, returning its root.
+pub fn spawn_demo_world(world: &mut World) -> Entity {
+    world.spawn((
+        TestComponent {
+            name: "Steve".to_owned()
+        },
+        TransformBundle::default()
+    )).with_children(|child_builder| {
+        child_builder.spawn((
+            TestComponent {
+                name: "Stove".to_owned()
+            },
+            TransformBundle::from_transform(Transform::from_xyz(1.0, 0.5, -1.3)),
+            LeafNode
+        )).with_children(|child_builder| {
+            child_builder.spawn(TransformBundle::from_transform(Transform::from_xyz(0.0, 5.0, 0.0)));
+        });
+    }).id()
+}
+
+/// Serializes `prefab` like a `.prefab` file, with [`TestComponent`] saved under its alias.
+pub fn serialize(prefab: &Prefab, app: &App) -> String {
+    serialize_prefab(prefab, app.world.resource::<AppTypeRegistry>()).unwrap()
+        .replace(std::any::type_name::<TestComponent>(), TEST_COMPONENT_ALIAS)
+}
+
+/// Reads a prefab from `bytes` as if it was the file at `path`, with the app's types & [`FormatMigrations`].
+pub fn deserialize(app: &App, path: &str, bytes: &[u8], mode: PrefabLoadMode) -> Result<Prefab, PrefabError> {
+    deserialize_prefab(bytes, std::path::Path::new(path), app.world.resource::<AppTypeRegistry>(), mode, app.world.resource::<FormatMigrations>())
+}
+
+/// Loads the prefab at `path` in `tests/assets`, `Err` with the final load state if it fails.
+pub fn load(app: &mut App, path: &str) -> Result<Handle<Prefab>, LoadState> {
+    let handle = app.world.resource::<AssetServer>().load(path);
+
+    for _ in 0..1000 {
+        app.update();
+
+        match app.world.resource::<AssetServer>().get_load_state(&handle) {
+            LoadState::Loaded => return Ok(handle),
+            LoadState::Failed => return Err(LoadState::Failed),
+            _ => std::thread::sleep(std::time::Duration::from_millis(1))
+        }
+    }
+
+    Err(app.world.resource::<AssetServer>().get_load_state(&handle))
+}
+
+/// Reads component `T` of `entity` back out of `prefab`.
+pub fn component<T: Component + FromReflect + GetTypeRegistration>(prefab: &Prefab, entity: u32) -> Option<T> {
+    prefab.scene.entities.iter()
+        .find(|dynamic_entity| dynamic_entity.entity == Entity::from_raw(entity))?
+        .components.iter()
+        .find(|component| component.type_name() == std::any::type_name::<T>())
+        .and_then(|component| T::from_reflect(&**component))
+}
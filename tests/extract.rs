@@ -0,0 +1,87 @@
+mod common;
+
+use bevy::{prelude::*, ecs::{entity::{EntityMapper, MapEntities}, reflect::ReflectMapEntities, world::FromWorld}};
+use bevy_scene_test::{serialize_prefab, error::PrefabError, extract::{ExtractOptions, extract_prefab}};
+
+/// Points at another entity, which has to be part of the same prefab.
+#[derive(Component, Reflect)]
+#[reflect(Component, MapEntities)]
+struct Target(Entity);
+impl FromWorld for Target {
+    fn from_world(_world: &mut World) -> Self {
+        Target(Entity::PLACEHOLDER)
+    }
+}
+impl MapEntities for Target {
+    fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+        self.0 = entity_mapper.get_or_reserve(self.0);
+    }
+}
+
+fn extract(world: &World, root: Entity) -> Result<String, PrefabError> {
+    let prefab = extract_prefab(world, root, &ExtractOptions::named("Test"))?;
+
+    Ok(serialize_prefab(&prefab, world.resource::<AppTypeRegistry>()).unwrap())
+}
+
+#[test]
+fn extraction_is_independent_of_runtime_ids() {
+    let mut app = common::app();
+    let root = common::spawn_demo_world(&mut app.world);
+
+    let mut other_app = common::app();
+    // Shift every entity of the second world to different ids, & free some to be reused out of order.
+    let spacers = (0..10).map(|_| other_app.world.spawn_empty().id()).collect::<Vec<_>>();
+    for spacer in spacers.into_iter().step_by(3) {
+        other_app.world.despawn(spacer);
+    }
+    let other_root = common::spawn_demo_world(&mut other_app.world);
+
+    assert_ne!(root, other_root);
+    assert_eq!(extract(&app.world, root).unwrap(), extract(&other_app.world, other_root).unwrap());
+}
+
+#[test]
+fn rejects_references_outside_the_hierarchy() {
+    let mut app = common::app();
+    app.register_type::<Target>();
+
+    let outside = app.world.spawn_empty().id();
+    let root = common::spawn_demo_world(&mut app.world);
+    app.world.entity_mut(root).insert(Target(outside));
+
+    assert!(matches!(extract(&app.world, root), Err(PrefabError::InvalidEntity { entity, .. }) if entity == Some(outside)));
+}
+
+#[test]
+fn maps_references_inside_the_hierarchy() {
+    let mut app = common::app();
+    app.register_type::<Target>();
+
+    let root = common::spawn_demo_world(&mut app.world);
+    let child = app.world.get::<Children>(root).unwrap()[0];
+    app.world.entity_mut(root).insert(Target(child));
+
+    let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Test")).unwrap();
+
+    let target = common::component::<Target>(&prefab, 0).map(|target| target.0);
+    assert_eq!(target, Some(Entity::from_raw(1)));
+}
+
+#[test]
+fn saves_up_to_max_depth() {
+    let mut app = common::app();
+    let root = common::spawn_demo_world(&mut app.world);
+
+    // The demo's child is a `LeafNode`, so nothing below depth 1 is saved anyway.
+    for max_depth in 0..2 {
+        let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Test").with_max_depth(max_depth)).unwrap();
+
+        assert_eq!(prefab.scene.entities.len(), max_depth + 1);
+        // The deepest entity saved is a boundary, its children aren't part of the prefab.
+        assert!(common::component::<Children>(&prefab, max_depth as u32).is_none());
+    }
+
+    let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Test").with_max_depth(0)).unwrap();
+    assert_eq!(common::component::<common::TestComponent>(&prefab, 0).map(|component| component.name), Some("Steve".to_owned()));
+}
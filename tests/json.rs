@@ -0,0 +1,19 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_scene_test::{json::serialize_json_prefab, lenient::PrefabLoadMode, tree::PrefabLayout};
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+#[test]
+fn round_trips_through_json() {
+    let app = common::app();
+    let prefab = common::deserialize(&app, "demo.prefab", FIXTURE.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+    for layout in [PrefabLayout::Flat, PrefabLayout::Tree] {
+        let json = serialize_json_prefab(&prefab, app.world.resource::<AppTypeRegistry>(), layout).unwrap();
+        let prefab = common::deserialize(&app, "demo.prefab.json", json.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+        assert_eq!(common::serialize(&prefab, &app), FIXTURE, "{layout:?} layout");
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_scene_test::{Prefab, error::PrefabError, lenient::{PrefabLoadMode, PrefabLoaderSettings}};
+
+use common::TestComponent;
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+/// Uses a type that isn't registered & a value of the wrong type.
+const PREFAB: &str = include_str!("assets/modded.prefab");
+
+#[test]
+fn loads_in_the_mode_set_after_the_plugin() {
+    let mut app = common::app();
+    assert!(common::load(&mut app, "modded.prefab").is_err());
+
+    let mut app = common::app();
+    app.world.resource_mut::<PrefabLoaderSettings>().set_mode(PrefabLoadMode::Lenient);
+
+    let handle = common::load(&mut app, "modded.prefab").unwrap();
+    assert_eq!(app.world.resource::<Assets<Prefab>>().get(&handle).unwrap().report.skipped.len(), 2);
+}
+
+#[test]
+fn skips_unknown_types_and_mistyped_values() {
+    let prefab = common::deserialize(&common::app(), "modded.prefab", PREFAB.as_bytes(), PrefabLoadMode::Lenient).unwrap();
+
+    let skipped = prefab.report.skipped.iter()
+        .map(|skipped| (skipped.entity, skipped.type_path.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(skipped, vec![
+        (Some(Entity::from_raw(0)), "some_mod::Jetpack"),
+        (Some(Entity::from_raw(1)), "tests::TestComponent")
+    ]);
+    assert!(prefab.report.skipped.iter().all(|skipped| skipped.path == Path::new("modded.prefab")));
+
+    // Everything else is still loaded, large integers included.
+    assert_eq!(common::component::<TestComponent>(&prefab, 0).unwrap().name, "Steve");
+    assert_eq!(common::component::<Name>(&prefab, 0).unwrap().as_str(), "Steve");
+    assert!(common::component::<TestComponent>(&prefab, 1).is_none());
+    assert_eq!(common::component::<Transform>(&prefab, 1).unwrap(), Transform::from_xyz(1.0, 0.5, -1.3));
+}
+
+#[test]
+fn strict_loads_fail_on_unknown_types() {
+    let result = common::deserialize(&common::app(), "modded.prefab", PREFAB.as_bytes(), PrefabLoadMode::Strict);
+
+    assert!(matches!(result, Err(PrefabError::UnknownType { type_path, .. }) if type_path == "some_mod::Jetpack"));
+}
+
+#[test]
+fn keeps_unit_structs() {
+    let app = common::app();
+    let prefab = common::deserialize(&app, "demo.prefab", FIXTURE.as_bytes(), PrefabLoadMode::Lenient).unwrap();
+
+    assert!(prefab.report.is_empty(), "{:?}", prefab.report);
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn keeps_json_values() {
+    use bevy_scene_test::{json::serialize_json_prefab, tree::PrefabLayout};
+
+    let app = common::app();
+    let prefab = common::deserialize(&app, "demo.prefab", FIXTURE.as_bytes(), PrefabLoadMode::Strict).unwrap();
+    let json = serialize_json_prefab(&prefab, app.world.resource::<AppTypeRegistry>(), PrefabLayout::Flat).unwrap();
+
+    let prefab = common::deserialize(&app, "demo.prefab.json", json.as_bytes(), PrefabLoadMode::Lenient).unwrap();
+
+    assert!(prefab.report.is_empty(), "{:?}", prefab.report);
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn reads_json_null_as_none() {
+    use bevy_scene_test::{extract::{ExtractOptions, extract_prefab}, json::serialize_json_prefab, tree::PrefabLayout};
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Label {
+        text: Option<String>
+    }
+
+    let mut app = common::app();
+    app.register_type::<Label>();
+    let root = app.world.spawn(Label::default()).id();
+
+    let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Unlabelled")).unwrap();
+    let json = serialize_json_prefab(&prefab, app.world.resource::<AppTypeRegistry>(), PrefabLayout::Flat).unwrap();
+    assert!(json.contains("null"), "{json}");
+
+    let prefab = common::deserialize(&app, "unlabelled.prefab.json", json.as_bytes(), PrefabLoadMode::Lenient).unwrap();
+
+    assert!(prefab.report.is_empty(), "{:?}", prefab.report);
+    assert_eq!(common::component::<Label>(&prefab, 0).unwrap().text, None);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_scene_test::{Prefab, LeafNode, extract::{ExtractOptions, extract_prefab}, instance::PrefabInstance, error::PrefabError, spawn::{PendingPrefab, PrefabSpawnFailed}};
+
+use common::TestComponent;
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+#[test]
+fn fixture_is_the_extracted_demo_world() {
+    let mut app = common::app();
+    let root = common::spawn_demo_world(&mut app.world);
+
+    let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Test")).unwrap();
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
+
+#[test]
+fn loads_every_component_of_the_demo_world() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "demo.prefab").unwrap();
+
+    let prefabs = app.world.resource::<Assets<Prefab>>();
+    let prefab = prefabs.get(&handle).unwrap();
+
+    let mut component_types = prefab.scene.entities.iter()
+        .map(|entity| entity.components.iter().map(|component| component.type_name()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    component_types.sort();
+
+    assert_eq!(component_types, vec![
+        vec![
+            std::any::type_name::<Children>(),
+            std::any::type_name::<Transform>(),
+            std::any::type_name::<TestComponent>()
+        ],
+        vec![
+            std::any::type_name::<Parent>(),
+            std::any::type_name::<LeafNode>(),
+            std::any::type_name::<Transform>(),
+            std::any::type_name::<TestComponent>()
+        ]
+    ]);
+    assert!(prefab.report.is_empty());
+}
+
+#[test]
+fn spawns_the_demo_world() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "demo.prefab").unwrap();
+
+    let instance = app.world.spawn(PendingPrefab {
+        handle,
+        transform: None
+    }).id();
+    app.update();
+
+    assert!(app.world.get::<PrefabInstance>(instance).is_some());
+    assert_eq!(app.world.get::<TestComponent>(instance).unwrap().name, "Steve");
+    assert_eq!(*app.world.get::<Transform>(instance).unwrap(), Transform::default());
+    assert!(app.world.get::<GlobalTransform>(instance).is_some());
+
+    let children = app.world.get::<Children>(instance).unwrap().iter().copied().collect::<Vec<_>>();
+    assert_eq!(children.len(), 1);
+
+    let child = app.world.entity(children[0]);
+    assert_eq!(child.get::<Parent>().unwrap().get(), instance);
+    assert_eq!(child.get::<TestComponent>().unwrap().name, "Stove");
+    assert_eq!(*child.get::<Transform>().unwrap(), Transform::from_xyz(1.0, 0.5, -1.3));
+    assert!(child.contains::<LeafNode>());
+    assert!(child.contains::<GlobalTransform>());
+    // The leaf's own children aren't part of the prefab.
+    assert!(!child.contains::<Children>());
+}
+
+#[test]
+fn reports_prefabs_that_fail_to_spawn() {
+    let mut app = common::app();
+    let handle = app.world.resource::<AssetServer>().load("missing.prefab");
+
+    let instance = app.world.spawn(PendingPrefab {
+        handle,
+        transform: None
+    }).id();
+
+    let mut failures = Vec::new();
+    for _ in 0..1000 {
+        app.update();
+
+        failures.extend(app.world.resource_mut::<Events<PrefabSpawnFailed>>().drain());
+        if !failures.is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    assert!(matches!(failures.as_slice(), [PrefabSpawnFailed { instance: failed, error: PrefabError::NotLoaded { .. } }] if *failed == instance));
+    assert!(app.world.get::<PendingPrefab>(instance).is_none());
+}
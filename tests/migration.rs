@@ -0,0 +1,197 @@
+mod common;
+
+use std::any::TypeId;
+
+use bevy::prelude::*;
+use bevy_scene_test::{Prefab, PrefabSerializer, error::PrefabError, extract::{ExtractOptions, extract_prefab}, format::{FormatMigration, FormatMigrations}, lenient::PrefabLoadMode, migration::{PrefabMigration, PrefabMigrations, ReflectPrefabMigrations, registration_for, split_version}, tree::PrefabLayout};
+
+use common::TestComponent;
+
+/// Called `health` before version 1.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, PrefabMigrations)]
+struct Health {
+    hp: f32
+}
+impl PrefabMigrations for Health {
+    const MIGRATIONS: &'static [PrefabMigration] = &[rename_health];
+}
+
+fn rename_health(value: ron::Value) -> Result<ron::Value, String> {
+    let ron::Value::Map(mut map) = value else {
+        return Err("expected a map".to_owned());
+    };
+
+    let hp = map.remove(&ron::Value::String("health".to_owned())).ok_or("missing health")?;
+    map.insert(ron::Value::String("hp".to_owned()), hp);
+
+    Ok(ron::Value::Map(map))
+}
+
+/// A prefab with a single [`Health`] saved under `key`.
+fn health_prefab(key: &str, value: &str) -> String {
+    format!(r#"(
+  version: 1,
+  name: "Healthy",
+  scene: {{
+    0: (
+      components: {{
+        "{key}": {value},
+      }},
+    ),
+  }},
+)"#)
+}
+
+fn health_app() -> App {
+    let mut app = common::app();
+    app.register_type::<Health>();
+
+    app
+}
+
+/// Version 0 prefabs called their name `title`.
+const VERSION_0: &str = include_str!("assets/old.prefab");
+
+fn rename_title(document: &mut ron::Value) -> Result<(), String> {
+    let ron::Value::Map(map) = document else {
+        return Err("expected a map".to_owned());
+    };
+
+    let title = map.remove(&ron::Value::String("title".to_owned())).ok_or("missing title")?;
+    map.insert(ron::Value::String("name".to_owned()), title);
+
+    Ok(())
+}
+
+fn load(ron: &str, migration: FormatMigration) -> Result<Prefab, PrefabError> {
+    let mut app = common::app();
+    app.world.resource_mut::<FormatMigrations>().add(0, migration);
+
+    common::deserialize(&app, "old.prefab", ron.as_bytes(), PrefabLoadMode::Strict)
+}
+
+#[test]
+fn migrates_outdated_documents() {
+    let prefab = load(VERSION_0, rename_title).unwrap();
+
+    assert_eq!(prefab.name, "Old");
+    assert_eq!(common::component::<TestComponent>(&prefab, 0).unwrap().name, "Steve");
+}
+
+#[test]
+fn loads_with_migrations_added_after_the_plugin() {
+    let mut app = common::app();
+    app.world.resource_mut::<FormatMigrations>().add(0, rename_title);
+
+    let handle = common::load(&mut app, "old.prefab").unwrap();
+
+    assert_eq!(app.world.resource::<Assets<Prefab>>().get(&handle).unwrap().name, "Old");
+}
+
+#[test]
+fn reports_failed_migrations() {
+    let result = load(VERSION_0, |_| Err("no way".to_owned()));
+
+    assert!(matches!(result, Err(PrefabError::Migration { message, .. }) if message.contains("no way")));
+}
+
+#[test]
+fn leaves_current_documents_alone() {
+    let prefab = load(include_str!("assets/demo.prefab"), |_| Err("ran on a current document".to_owned())).unwrap();
+
+    assert_eq!(prefab.name, "Test");
+}
+
+#[test]
+fn splits_versions_off_keys() {
+    assert_eq!(split_version("my_game::Health@2"), Ok(("my_game::Health", 2)));
+    assert_eq!(split_version("my_game::Health"), Ok(("my_game::Health", 0)));
+    assert!(split_version("my_game::Health@two").is_err());
+}
+
+#[test]
+fn finds_types_by_alias() {
+    let app = common::app();
+    let type_registry = app.world.resource::<AppTypeRegistry>().read();
+
+    let registration = registration_for(&type_registry, common::TEST_COMPONENT_ALIAS).unwrap();
+
+    assert_eq!(registration.type_id(), TypeId::of::<TestComponent>());
+    assert!(registration_for(&type_registry, "tests::Missing").is_none());
+}
+
+#[test]
+fn reads_overrides_saved_under_an_alias() {
+    let app = common::app();
+    let ron = format!(r#"(
+  version: 1,
+  name: "Level",
+  scene: {{
+    0: (
+      components: {{}},
+    ),
+  }},
+  nested: {{
+    0: (
+      path: "demo.prefab",
+      overrides: [
+        (
+          source: 0,
+          component: "{}",
+          path: ".name",
+          value: Some({{
+            "alloc::string::String": "Bob",
+          }}),
+        ),
+      ],
+    ),
+  }},
+)"#, common::TEST_COMPONENT_ALIAS);
+
+    let prefab = common::deserialize(&app, "level.prefab", ron.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+    assert_eq!(prefab.nested[0].overrides[0].component, std::any::type_name::<TestComponent>());
+}
+
+#[test]
+fn migrates_old_component_values() {
+    let app = health_app();
+    let type_path = std::any::type_name::<Health>();
+
+    let old = health_prefab(type_path, "(health: 5.0)");
+    let prefab = common::deserialize(&app, "old.prefab", old.as_bytes(), PrefabLoadMode::Strict).unwrap();
+    assert_eq!(common::component::<Health>(&prefab, 0).unwrap().hp, 5.0);
+
+    let current = health_prefab(&format!("{type_path}@1"), "(hp: 3.0)");
+    let prefab = common::deserialize(&app, "current.prefab", current.as_bytes(), PrefabLoadMode::Strict).unwrap();
+    assert_eq!(common::component::<Health>(&prefab, 0).unwrap().hp, 3.0);
+
+    let newer = health_prefab(&format!("{type_path}@2"), "(hp: 3.0)");
+    let result = common::deserialize(&app, "newer.prefab", newer.as_bytes(), PrefabLoadMode::Strict);
+    assert!(matches!(result, Err(PrefabError::Migration { .. })));
+}
+
+#[test]
+fn saves_versions_in_any_order() {
+    let mut app = health_app();
+    let root = app.world.spawn(Health {
+        hp: 7.0
+    }).id();
+    let prefab = extract_prefab(&app.world, root, &ExtractOptions::named("Healthy")).unwrap();
+
+    for canonical in [true, false] {
+        let prefab_serializer = PrefabSerializer {
+            prefab: &prefab,
+            registry: app.world.resource::<AppTypeRegistry>(),
+            canonical,
+            layout: PrefabLayout::Flat
+        };
+        let saved = ron::to_string(&prefab_serializer).unwrap();
+        assert!(saved.contains(&format!("\"{}@1\"", std::any::type_name::<Health>())), "{saved}");
+
+        // Read back as the current version, without running the migrations again.
+        let prefab = common::deserialize(&app, "healthy.prefab", saved.as_bytes(), PrefabLoadMode::Strict).unwrap();
+        assert_eq!(common::component::<Health>(&prefab, 0).unwrap().hp, 7.0);
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_scene_test::{Prefab, LeafNode, error::PrefabError, extract::{ExtractOptions, extract_prefab}, instance::PrefabInstance, spawn::{PendingPrefab, PrefabSpawnFailed}};
+
+use common::TestComponent;
+
+/// A level holding an instance of `demo.prefab` whose root was renamed.
+const FIXTURE: &str = include_str!("assets/level.prefab");
+
+/// Spawns `handle` under `parent` & waits for it & the prefabs nested in it to be written into the world.
+fn spawn_instance(app: &mut App, handle: Handle<Prefab>, parent: Option<Entity>) -> Entity {
+    let mut instance = app.world.spawn(PendingPrefab {
+        handle,
+        transform: None
+    });
+    if let Some(parent) = parent {
+        instance.set_parent(parent);
+    }
+    let instance = instance.id();
+
+    for _ in 0..1000 {
+        app.update();
+
+        if !app.world.entity(instance).contains::<PendingPrefab>() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    instance
+}
+
+#[test]
+fn saves_nested_instances_as_references() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "demo.prefab").unwrap();
+
+    let level = app.world.spawn((
+        TestComponent {
+            name: "Level".to_owned()
+        },
+        TransformBundle::default()
+    )).id();
+    let instance = spawn_instance(&mut app, handle, Some(level));
+    app.world.get_mut::<TestComponent>(instance).unwrap().name = "Bob".to_owned();
+
+    let prefab = extract_prefab(&app.world, level, &ExtractOptions::named("Level")).unwrap();
+    assert_eq!(common::serialize(&prefab, &app), FIXTURE);
+}
+
+#[test]
+fn expands_nested_prefabs_on_spawn() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "level.prefab").unwrap();
+
+    let level = spawn_instance(&mut app, handle, None);
+    assert_eq!(app.world.get::<TestComponent>(level).unwrap().name, "Level");
+
+    let instance = app.world.get::<Children>(level).unwrap()[0];
+    assert!(app.world.get::<PrefabInstance>(instance).is_some());
+    // The override saved with the reference is applied on top of the nested prefab.
+    assert_eq!(app.world.get::<TestComponent>(instance).unwrap().name, "Bob");
+    assert_eq!(app.world.get::<Parent>(instance).map(Parent::get), Some(level));
+
+    let leaf = app.world.get::<Children>(instance).unwrap()[0];
+    assert_eq!(app.world.get::<TestComponent>(leaf).unwrap().name, "Stove");
+    assert!(app.world.entity(leaf).contains::<LeafNode>());
+}
+
+#[test]
+fn refuses_prefabs_nesting_themselves() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "cycle_a.prefab").unwrap();
+
+    let instance = spawn_instance(&mut app, handle, None);
+
+    let failures = app.world.resource_mut::<Events<PrefabSpawnFailed>>().drain().collect::<Vec<_>>();
+    assert!(matches!(failures.as_slice(), [PrefabSpawnFailed { instance: failed, error: PrefabError::Cycle { chain } }] if *failed == instance && chain.len() == 3));
+    assert!(app.world.get::<PrefabInstance>(instance).is_none());
+    assert!(app.world.get::<Children>(instance).is_none());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_scene_test::{Prefab, LeafNode, overrides::prefab_overrides, spawn::PendingPrefab};
+
+use common::TestComponent;
+
+/// Loads `demo.prefab` & spawns an instance of it, returning the handle & the instance.
+fn spawn_demo(app: &mut App) -> (Handle<Prefab>, Entity) {
+    let handle = common::load(app, "demo.prefab").unwrap();
+
+    let instance = app.world.spawn(PendingPrefab {
+        handle: handle.clone(),
+        transform: None
+    }).id();
+    app.update();
+
+    (handle, instance)
+}
+
+/// Edits the loaded prefab in place, which is seen as a modified asset just like a changed file.
+fn modify_prefab(app: &mut App, handle: &Handle<Prefab>, edit: impl FnOnce(&mut Prefab)) {
+    edit(app.world.resource_mut::<Assets<Prefab>>().get_mut(handle).unwrap());
+
+    // One update to send the asset event, one for the reload system to see it.
+    app.update();
+    app.update();
+}
+
+/// Replaces component `T` of `entity` in `prefab`.
+fn set_component<T: Component + Reflect>(prefab: &mut Prefab, entity: u32, component: T) {
+    let dynamic_entity = prefab.scene.entities.iter_mut().find(|dynamic_entity| dynamic_entity.entity == Entity::from_raw(entity)).unwrap();
+    dynamic_entity.components.retain(|existing| existing.type_name() != std::any::type_name::<T>());
+    dynamic_entity.components.push(Box::new(component));
+}
+
+fn child_of(app: &App, instance: Entity) -> Entity {
+    app.world.get::<Children>(instance).unwrap()[0]
+}
+
+#[test]
+fn keeps_runtime_children_of_leaf_nodes() {
+    let mut app = common::app();
+    let (handle, instance) = spawn_demo(&mut app);
+
+    let leaf = child_of(&app, instance);
+    assert!(app.world.entity(leaf).contains::<LeafNode>());
+    let runtime_child = app.world.spawn(TransformBundle::default()).set_parent(leaf).id();
+    // Unlike the leaf's, the root's `Children` are part of the prefab & rewritten by the reload.
+    let runtime_sibling = app.world.spawn(TransformBundle::default()).set_parent(instance).id();
+
+    modify_prefab(&mut app, &handle, |prefab| set_component(prefab, 1, TestComponent {
+        name: "Oven".to_owned()
+    }));
+
+    assert_eq!(app.world.get::<Children>(instance).map(|children| children.to_vec()), Some(vec![leaf, runtime_sibling]));
+    assert_eq!(app.world.get::<TestComponent>(leaf).unwrap().name, "Oven");
+    assert_eq!(app.world.get::<Children>(leaf).map(|children| children.to_vec()), Some(vec![runtime_child]));
+    assert_eq!(app.world.get::<Parent>(runtime_child).map(Parent::get), Some(leaf));
+}
+
+#[test]
+fn keeps_overrides_of_modified_prefabs() {
+    let mut app = common::app();
+    let (handle, instance) = spawn_demo(&mut app);
+    let leaf = child_of(&app, instance);
+
+    app.world.get_mut::<TestComponent>(instance).unwrap().name = "Steven".to_owned();
+    app.world.get_mut::<Transform>(leaf).unwrap().translation.x = 4.0;
+
+    modify_prefab(&mut app, &handle, |prefab| {
+        set_component(prefab, 0, Transform::from_xyz(0.0, 3.0, 0.0));
+        set_component(prefab, 1, TestComponent {
+            name: "Oven".to_owned()
+        });
+        set_component(prefab, 1, Transform::from_xyz(1.0, 2.0, 3.0));
+    });
+
+    // Overridden fields keep the instance's values, everything else takes on the prefab's new ones.
+    assert_eq!(app.world.get::<TestComponent>(instance).unwrap().name, "Steven");
+    assert_eq!(app.world.get::<Transform>(instance).unwrap().translation, Vec3::new(0.0, 3.0, 0.0));
+    assert_eq!(app.world.get::<TestComponent>(leaf).unwrap().name, "Oven");
+    assert_eq!(app.world.get::<Transform>(leaf).unwrap().translation, Vec3::new(4.0, 2.0, 3.0));
+
+    let mut overrides = prefab_overrides(&app.world, instance).into_iter()
+        .map(|prefab_override| (prefab_override.source.index(), prefab_override.path))
+        .collect::<Vec<_>>();
+    overrides.sort();
+    assert_eq!(overrides, vec![(0, ".name".to_owned()), (1, ".translation.x".to_owned())]);
+}
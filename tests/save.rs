@@ -0,0 +1,118 @@
+mod common;
+
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::{prelude::*, asset::FileAssetIo};
+use bevy_scene_test::{error::PrefabError, save::{PrefabSaveFailed, PrefabSaveSettings, PrefabSaved, SavePrefabRequest, save_prefab}};
+
+use common::TestComponent;
+
+const FIXTURE: &str = include_str!("assets/demo.prefab");
+
+/// An app saving to an empty folder of its own instead of `tests/assets`.
+fn app(test: &str) -> (App, PathBuf) {
+    let folder = Path::new(env!("CARGO_TARGET_TMPDIR")).join("save").join(test);
+    let _ = fs::remove_dir_all(&folder);
+
+    let mut app = common::app();
+    app.world.resource_mut::<PrefabSaveSettings>().asset_folder = folder.clone();
+
+    (app, folder)
+}
+
+/// `demo.prefab` as saved under `name`, which the saved prefab is named after.
+fn fixture_named(name: &str) -> String {
+    FIXTURE.replace("name: \"Test\"", &format!("name: \"{name}\""))
+}
+
+fn saved(folder: &Path, path: &str) -> String {
+    fs::read_to_string(folder.join(path)).unwrap()
+        .replace(std::any::type_name::<TestComponent>(), common::TEST_COMPONENT_ALIAS)
+}
+
+/// The name of the root's [`TestComponent`] in the prefab saved at `path`.
+#[cfg(any(feature = "json", feature = "binary"))]
+fn saved_root_name(app: &App, folder: &Path, path: &str) -> Option<String> {
+    let prefab = common::deserialize(app, path, &fs::read(folder.join(path)).unwrap(), bevy_scene_test::lenient::PrefabLoadMode::Strict).unwrap();
+
+    common::component::<TestComponent>(&prefab, 0).map(|component| component.name)
+}
+
+#[test]
+fn saves_to_the_asset_folder_by_default() {
+    let app = common::app();
+
+    assert_eq!(app.world.resource::<PrefabSaveSettings>().asset_folder, FileAssetIo::get_base_path().join("tests/assets"));
+}
+
+#[test]
+fn keeps_a_backup_of_the_previous_version() {
+    let (mut app, folder) = app("backup");
+    let root = common::spawn_demo_world(&mut app.world);
+
+    save_prefab(&app.world, root, "levels/demo.prefab").unwrap();
+    assert_eq!(saved(&folder, "levels/demo.prefab"), fixture_named("demo"));
+    assert!(!folder.join("levels/demo.prefab.bak").exists());
+
+    app.world.get_mut::<TestComponent>(root).unwrap().name = "Steven".to_owned();
+    save_prefab(&app.world, root, "levels/demo.prefab").unwrap();
+
+    assert_eq!(saved(&folder, "levels/demo.prefab.bak"), fixture_named("demo"));
+    assert_eq!(saved(&folder, "levels/demo.prefab"), fixture_named("demo").replace("\"Steve\"", "\"Steven\""));
+    assert!(!folder.join("levels/demo.prefab.tmp").exists());
+}
+
+#[test]
+fn saves_in_the_format_of_the_extension() {
+    let (mut app, folder) = app("format");
+    let root = common::spawn_demo_world(&mut app.world);
+
+    let json = save_prefab(&app.world, root, "demo.prefab.json");
+    #[cfg(feature = "json")]
+    {
+        json.unwrap();
+        assert_eq!(saved_root_name(&app, &folder, "demo.prefab.json"), Some("Steve".to_owned()));
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        assert!(matches!(json, Err(PrefabError::FeatureDisabled { feature: "json", .. })));
+        assert!(!folder.join("demo.prefab.json").exists());
+    }
+
+    let binary = save_prefab(&app.world, root, "demo.prefab.bin");
+    #[cfg(feature = "binary")]
+    {
+        binary.unwrap();
+        assert_eq!(saved_root_name(&app, &folder, "demo.prefab.bin"), Some("Steve".to_owned()));
+    }
+    #[cfg(not(feature = "binary"))]
+    assert!(matches!(binary, Err(PrefabError::FeatureDisabled { feature: "binary", .. })));
+}
+
+#[test]
+fn answers_save_requests_with_events() {
+    let (mut app, folder) = app("requests");
+    let root = common::spawn_demo_world(&mut app.world);
+    let missing = app.world.spawn_empty().id();
+    app.world.despawn(missing);
+
+    app.world.send_event(SavePrefabRequest {
+        root,
+        path: "demo.prefab".into(),
+        name: "Test".to_owned()
+    });
+    app.world.send_event(SavePrefabRequest {
+        root: missing,
+        path: "missing.prefab".into(),
+        name: "Missing".to_owned()
+    });
+    app.update();
+
+    let saved_roots = app.world.resource_mut::<Events<PrefabSaved>>().drain().map(|saved| saved.root).collect::<Vec<_>>();
+    assert_eq!(saved_roots, vec![root]);
+    assert_eq!(saved(&folder, "demo.prefab"), FIXTURE);
+
+    let failures = app.world.resource_mut::<Events<PrefabSaveFailed>>().drain().collect::<Vec<_>>();
+    assert!(matches!(failures.as_slice(), [PrefabSaveFailed { root, error: PrefabError::InvalidEntity { .. }, .. }] if *root == missing));
+    assert!(!folder.join("missing.prefab").exists());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_scene_test::{error::PrefabError, format::FormatMigrations, lenient::PrefabLoadMode, tree::{PrefabLayout, convert_layout}};
+
+/// A root whose children are listed in the opposite order of their ids.
+const REVERSED_CHILDREN: &str = r#"(
+  version: 1,
+  name: "Reversed",
+  scene: {
+    1: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (3),
+      },
+    ),
+    2: (
+      components: {
+        "bevy_hierarchy::components::parent::Parent": (3),
+      },
+    ),
+    3: (
+      components: {
+        "bevy_hierarchy::components::children::Children": ([2, 1]),
+      },
+    ),
+  },
+)"#;
+
+/// Nests a prefab on entity 5, which isn't in its scene.
+const MISSING_NESTED_ENTITY: &str = r#"(
+  version: 1,
+  name: "Missing",
+  scene: {
+    0: (
+      components: {},
+    ),
+  },
+  nested: {
+    5: (
+      path: "demo.prefab",
+      overrides: [],
+    ),
+  },
+)"#;
+
+fn convert(app: &App, path: &str, source: &str, layout: PrefabLayout) -> Result<String, PrefabError> {
+    convert_layout(source.as_bytes(), Path::new(path), layout, app.world.resource::<AppTypeRegistry>(), app.world.resource::<FormatMigrations>())
+}
+
+#[test]
+fn renumbers_entities_in_hierarchy_order() {
+    let app = common::app();
+    let tree = convert(&app, "reversed.prefab", REVERSED_CHILDREN, PrefabLayout::Tree).unwrap();
+    let prefab = common::deserialize(&app, "reversed.prefab", tree.as_bytes(), PrefabLoadMode::Strict).unwrap();
+
+    let entities = prefab.scene.entities.iter().map(|entity| entity.entity.index()).collect::<Vec<_>>();
+    assert_eq!(entities, vec![0, 1, 2]);
+
+    let children = common::component::<Children>(&prefab, 0).unwrap();
+    assert_eq!(&*children, &[Entity::from_raw(1), Entity::from_raw(2)]);
+    assert_eq!(common::component::<Parent>(&prefab, 2).map(|parent| parent.get()), Some(Entity::from_raw(0)));
+
+    // Converting back keeps the new ids.
+    let flat = convert(&app, "reversed.prefab", &tree, PrefabLayout::Flat).unwrap();
+    assert_eq!(convert(&app, "reversed.prefab", &flat, PrefabLayout::Tree).unwrap(), tree);
+}
+
+#[test]
+fn rejects_nested_prefabs_on_missing_entities() {
+    let app = common::app();
+
+    assert!(matches!(
+        convert(&app, "missing.prefab", MISSING_NESTED_ENTITY, PrefabLayout::Tree),
+        Err(PrefabError::InvalidEntity { entity: Some(entity), .. }) if entity == Entity::from_raw(5)
+    ));
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use bevy::{prelude::*, asset::LoadState};
+use bevy_scene_test::Prefab;
+
+use common::TestComponent;
+
+
+#[test]
+fn flattens_a_variant_onto_its_base() {
+    let mut app = common::app();
+    let handle = common::load(&mut app, "variant.prefab").unwrap();
+
+    let prefabs = app.world.resource::<Assets<Prefab>>();
+    let prefab = prefabs.get(&handle).unwrap();
+
+    assert_eq!(prefab.name, "Variant");
+    assert_eq!(prefab.bases, vec!["demo.prefab".to_owned()]);
+
+    let mut entities = prefab.scene.entities.iter().map(|entity| entity.entity.index()).collect::<Vec<_>>();
+    entities.sort();
+    assert_eq!(entities, vec![0, 2]);
+
+    // Components of the variant replace the base's, the ones it doesn't mention are kept & overrides apply on top.
+    assert_eq!(common::component::<TestComponent>(prefab, 0).unwrap().name, "Steven");
+    assert_eq!(common::component::<Transform>(prefab, 0).unwrap(), Transform::from_xyz(0.0, 2.0, 0.0));
+
+    // The removed entity is gone from its parent's children, the added one takes its place.
+    let children = common::component::<Children>(prefab, 0).unwrap();
+    assert_eq!(&*children, &[Entity::from_raw(2)]);
+    assert_eq!(common::component::<Parent>(prefab, 2).unwrap().get(), Entity::from_raw(0));
+    assert_eq!(common::component::<TestComponent>(prefab, 2).unwrap().name, "Pot");
+}
+
+#[test]
+fn rejects_added_entities_reusing_base_ids() {
+    let mut app = common::app();
+
+    assert_eq!(common::load(&mut app, "colliding_variant.prefab"), Err(LoadState::Failed));
+}